@@ -0,0 +1,56 @@
+//! How multi-threaded MCTS scales with the number of threads.
+//!
+//! For every thread count (doubling up to the number of cores) and both kinds of parallelism,
+//! this measures how many playouts a search gets through and how a multi-threaded engine
+//! scores against a single-threaded one, both with the same time per move.
+//!
+//! Run with `cargo run --release -p ai --example parallel_scaling [games] [milliseconds per move]`
+use std::time::Duration;
+
+use ai::agent::{play_match, MctsAgent};
+use ai::limits::SearchLimits;
+use ai::mcts::{Mcts, MctsConfig, Parallelism};
+use super_ttt::Game;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let games: usize = args.next().map_or(10, |arg| arg.parse().expect("games"));
+    let time = Duration::from_millis(args.next().map_or(100, |arg| arg.parse().expect("time")));
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+
+    let single = MctsConfig {
+        limits: SearchLimits::time(time),
+        ..Default::default()
+    };
+    println!("{cores} cores, {games} games per row at {time:?} per move against 1 thread\n");
+    println!("parallelism  threads  playouts/move  score");
+    for parallelism in [Parallelism::Root, Parallelism::Tree] {
+        let mut threads = 1;
+        while threads <= cores.max(2) {
+            let config = MctsConfig {
+                threads,
+                parallelism,
+                ..single
+            };
+
+            let mut mcts = Mcts::new(MctsConfig {
+                reuse_tree: false,
+                ..config
+            });
+            mcts.search(Game::new());
+            let playouts = mcts.root_visits();
+
+            let result = play_match(
+                games,
+                |_| MctsAgent::new(config),
+                |_| MctsAgent::new(single),
+            );
+            println!(
+                "{:<11}  {threads:>7}  {playouts:>13}  {:>4.1}%",
+                format!("{parallelism:?}"),
+                100.0 * result.score()
+            );
+            threads *= 2;
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! How many random playouts per second [`simulate_game`] and the [`playout`](ai::playout)
+//! functions get through, playing the same games from the same seed.
+//!
+//! Run with `cargo run --release -p ai --example playout_throughput [playouts]`
+use std::hint::black_box;
+use std::time::Instant;
+
+use ai::playout::{playout, playout_moves, playout_with};
+use ai::rng::{seeded, GameRng};
+use ai::{simulate_game, MoveList};
+use super_ttt::Game;
+
+fn main() {
+    let playouts: usize = std::env::args()
+        .nth(1)
+        .map_or(100_000, |arg| arg.parse().expect("playouts"));
+
+    let measure = |name: &str, mut run: Box<dyn FnMut(&mut GameRng) -> i64>| {
+        let mut rng = seeded(0);
+        let start = Instant::now();
+        let mut total = 0;
+        for _ in 0..playouts {
+            total += run(&mut rng);
+        }
+        let elapsed = start.elapsed();
+        black_box(total);
+        println!(
+            "{name:<16} {:>10.0} playouts/s",
+            playouts as f64 / elapsed.as_secs_f64()
+        );
+    };
+
+    println!("{playouts} playouts each\n");
+    measure(
+        "simulate_game",
+        Box::new(|rng| simulate_game(Game::new(), rng).1 as i64),
+    );
+    measure(
+        "playout",
+        Box::new(|rng| playout(Game::new(), rng).sign() as i64),
+    );
+    let mut moves = MoveList::new();
+    measure(
+        "playout_moves",
+        Box::new(move |rng| {
+            playout_moves(Game::new(), rng, &mut moves);
+            moves.len() as i64
+        }),
+    );
+    measure(
+        "playout_with",
+        Box::new(|rng| {
+            let mut visited = 0;
+            playout_with(Game::new(), rng, |_, _| visited += 1);
+            visited
+        }),
+    );
+}
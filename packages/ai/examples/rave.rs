@@ -0,0 +1,45 @@
+//! How MCTS with RAVE scores against plain UCT, both with the same number of iterations per move.
+//!
+//! Run with `cargo run --release -p ai --example rave [games] [iterations per move]`
+use ai::agent::{play_match, MctsAgent};
+use ai::limits::SearchLimits;
+use ai::mcts::{MctsConfig, RaveSchedule};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let games: usize = args.next().map_or(40, |arg| arg.parse().expect("games"));
+    let iterations: usize = args
+        .next()
+        .map_or(500, |arg| arg.parse().expect("iterations"));
+    let schedules = [
+        RaveSchedule::Equivalence(100.0),
+        RaveSchedule::Equivalence(1_000.0),
+        RaveSchedule::MinimumMse(0.05),
+        RaveSchedule::MinimumMse(0.2),
+    ];
+
+    println!("{games} games per row at {iterations} iterations per move against UCT\n");
+    println!("schedule             score");
+    for schedule in schedules {
+        let uct = |game: usize| MctsConfig {
+            limits: SearchLimits::iterations(iterations),
+            seed: Some(game as u64),
+            ..Default::default()
+        };
+        let result = play_match(
+            games,
+            |game| {
+                MctsAgent::new(MctsConfig {
+                    rave: Some(schedule),
+                    ..uct(game)
+                })
+            },
+            |game| MctsAgent::new(uct(game)),
+        );
+        println!(
+            "{:<20} {:>4.1}%",
+            format!("{schedule:?}"),
+            100.0 * result.score()
+        );
+    }
+}
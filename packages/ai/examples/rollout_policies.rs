@@ -0,0 +1,54 @@
+//! How fast every rollout policy plays out games, and how MCTS with it scores against
+//! MCTS with uniformly random playouts, both with the same number of iterations per move.
+//!
+//! Run with `cargo run --release -p ai --example rollout_policies [games] [iterations per move]`
+use std::sync::Arc;
+use std::time::Instant;
+
+use ai::agent::{play_match, MctsAgent};
+use ai::limits::SearchLimits;
+use ai::mcts::MctsConfig;
+use ai::playout::rollout;
+use ai::policy::{AvoidGifts, EpsilonGreedy, RolloutPolicy, Uniform, WinIfPossible, WinOrBlock};
+use ai::rng::seeded;
+use super_ttt::Game;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let games: usize = args.next().map_or(20, |arg| arg.parse().expect("games"));
+    let iterations: usize = args
+        .next()
+        .map_or(300, |arg| arg.parse().expect("iterations"));
+    let policies: [(&str, Arc<dyn RolloutPolicy + Send + Sync>); 5] = [
+        ("uniform", Arc::new(Uniform)),
+        ("win", Arc::new(WinIfPossible)),
+        ("win-or-block", Arc::new(WinOrBlock)),
+        ("avoid-gifts", Arc::new(AvoidGifts)),
+        ("greedy", Arc::new(EpsilonGreedy::new(0.1))),
+    ];
+
+    println!("{games} games per row at {iterations} iterations per move against uniform\n");
+    println!("policy        playouts/s  score");
+    for (name, policy) in policies {
+        let mut rng = seeded(0);
+        let start = Instant::now();
+        let mut playouts = 0;
+        while playouts < 1_000 || start.elapsed().as_millis() < 500 {
+            rollout(Game::new(), &policy, &mut rng);
+            playouts += 1;
+        }
+        let speed = playouts as f64 / start.elapsed().as_secs_f64();
+
+        let config = |game: usize| MctsConfig {
+            limits: SearchLimits::iterations(iterations),
+            seed: Some(game as u64),
+            ..Default::default()
+        };
+        let result = play_match(
+            games,
+            |game| MctsAgent::with_policy(config(game), Arc::clone(&policy)),
+            |game| MctsAgent::new(config(game)),
+        );
+        println!("{name:<12} {speed:>11.0}  {:>4.1}%", 100.0 * result.score());
+    }
+}
@@ -0,0 +1,30 @@
+//! Solve a position exactly with proof-number search.
+//!
+//! Run with `cargo run --release -p ai --example solve "<position string>" [max nodes]`
+use ai::limits::SearchLimits;
+use ai::pns::ProofNumberSearch;
+use super_ttt::notation::move_to_string;
+use super_ttt::Game;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let game = Game::from_position_string(&args.next().expect("a position string"))
+        .expect("not a valid position string");
+    let nodes = args.next().map(|arg| arg.parse().expect("nodes"));
+    println!("{game}");
+
+    let mut solver = ProofNumberSearch::new(SearchLimits {
+        nodes,
+        ..Default::default()
+    });
+    match solver.solve(game) {
+        Some(solution) => {
+            print!("{} for {}", solution.outcome, game.current_player);
+            if let Some(move_) = solution.proof_move {
+                print!(", playing {}", move_to_string(move_));
+            }
+            println!(" ({} nodes in {:?})", solution.nodes, solution.time);
+        }
+        None => println!("Not solved within {} nodes", nodes.unwrap_or_default()),
+    }
+}
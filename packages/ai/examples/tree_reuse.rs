@@ -0,0 +1,81 @@
+//! Compare MCTS with and without tree reuse at the same number of iterations per move.
+//!
+//! Run with `cargo run --release -p ai --example tree_reuse [games] [iterations]`
+use std::cell::Cell;
+use std::time::Instant;
+
+use ai::agent::{play_match, Agent, MctsAgent};
+use ai::limits::SearchLimits;
+use ai::mcts::MctsConfig;
+use ai::Move;
+use super_ttt::Game;
+
+/// An [`MctsAgent`] that counts how many playouts it already had for every position
+/// it was asked to move in
+struct Reusing<'a> {
+    agent: MctsAgent,
+    /// Playouts the engine already had for the position when it was asked to move
+    inherited: &'a Cell<u64>,
+    searches: &'a Cell<usize>,
+}
+
+impl Agent for Reusing<'_> {
+    fn choose_move(&mut self, game: &Game) -> Move {
+        if self.agent.mcts().root() == Some(*game) {
+            self.inherited
+                .set(self.inherited.get() + u64::from(self.agent.mcts().root_visits()));
+        }
+        self.searches.set(self.searches.get() + 1);
+        self.agent.choose_move(game)
+    }
+    fn new_game(&mut self, game: &Game) {
+        self.agent.new_game(game)
+    }
+    fn opponent_moved(&mut self, move_: Move, game: &Game) {
+        self.agent.opponent_moved(move_, game)
+    }
+    fn name(&self) -> String {
+        self.agent.name()
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let games: usize = args.next().map_or(20, |arg| arg.parse().expect("games"));
+    let iterations: usize = args
+        .next()
+        .map_or(500, |arg| arg.parse().expect("iterations"));
+
+    let config = MctsConfig {
+        limits: SearchLimits::iterations(iterations),
+        ..Default::default()
+    };
+    let (inherited, searches) = (Cell::new(0), Cell::new(0));
+    let start = Instant::now();
+    let result = play_match(
+        games,
+        |_| Reusing {
+            agent: MctsAgent::new(config),
+            inherited: &inherited,
+            searches: &searches,
+        },
+        |_| {
+            MctsAgent::new(MctsConfig {
+                reuse_tree: false,
+                ..config
+            })
+        },
+    );
+    println!(
+        "{games} games at {iterations} iterations per move ({:?})",
+        start.elapsed()
+    );
+    println!("Reusing the tree won:       {}", result.wins);
+    println!("Searching from scratch won: {}", result.losses);
+    println!("Ties:                       {}", result.ties);
+    println!("Score with tree reuse: {:.1}%", 100.0 * result.score());
+    println!(
+        "Playouts kept from earlier searches: {:.0} per move on average, on top of the {iterations} searched",
+        inherited.get() as f64 / searches.get() as f64
+    );
+}
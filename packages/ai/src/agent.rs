@@ -0,0 +1,398 @@
+//! A common interface for everything that can play super tic tac toe.
+//!
+//! Frontends, analysis tools and tournaments only need to know about [`Agent`],
+//! so any engine can be plugged into any of them.
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::seq::SliceRandom;
+use super_ttt::{Game, GameState, Move, Player};
+
+use crate::alphabeta::{AlphaBeta, SearchInfo};
+use crate::apply_move;
+use crate::book::Book;
+use crate::eval::{Evaluator, HandcraftedEvaluator};
+use crate::limits::SearchLimits;
+use crate::mcts::{Mcts, MctsConfig};
+use crate::policy::{RolloutPolicy, Uniform};
+use crate::puct::{PolicyValue, Puct, PuctConfig};
+use crate::rng::{self, GameRng};
+
+/// Something that chooses moves.
+///
+/// Only [`Agent::choose_move`] is required. The other methods are notifications an agent
+/// can use to keep its own state (like a search tree) in sync with the game; they do nothing by default.
+pub trait Agent {
+    /// Pick a move for [`Game::current_player`]. Only called while the game is in progress,
+    /// and the move must be one of [`Game::legal_moves`]
+    fn choose_move(&mut self, game: &Game) -> Move;
+    /// A new game is starting from `game`
+    fn new_game(&mut self, _game: &Game) {}
+    /// The opponent played `move_`, which led to `game`
+    fn opponent_moved(&mut self, _move_: Move, _game: &Game) {}
+    /// How much time this agent has left on its clock
+    fn time_left(&mut self, _remaining: Duration) {}
+    /// A short name for display purposes
+    fn name(&self) -> String;
+}
+
+impl<A: Agent + ?Sized> Agent for Box<A> {
+    fn choose_move(&mut self, game: &Game) -> Move {
+        (**self).choose_move(game)
+    }
+    fn new_game(&mut self, game: &Game) {
+        (**self).new_game(game)
+    }
+    fn opponent_moved(&mut self, move_: Move, game: &Game) {
+        (**self).opponent_moved(move_, game)
+    }
+    fn time_left(&mut self, remaining: Duration) {
+        (**self).time_left(remaining)
+    }
+    fn name(&self) -> String {
+        (**self).name()
+    }
+}
+
+/// Play `x` against `o` from `game` until the game ends, notifying both agents along the way
+pub fn play_game(
+    x: &mut (impl Agent + ?Sized),
+    o: &mut (impl Agent + ?Sized),
+    game: Game,
+) -> GameState {
+    play_game_with(x, o, game, |_| {})
+}
+/// Like [`play_game`], calling `on_position` with every position before its move is chosen
+pub fn play_game_with(
+    x: &mut (impl Agent + ?Sized),
+    o: &mut (impl Agent + ?Sized),
+    mut game: Game,
+    mut on_position: impl FnMut(&Game),
+) -> GameState {
+    x.new_game(&game);
+    o.new_game(&game);
+    while game.get_winner() == GameState::InProgress {
+        on_position(&game);
+        let move_ = match game.current_player {
+            Player::X => x.choose_move(&game),
+            Player::O => o.choose_move(&game),
+        };
+        game = apply_move(move_, game).expect("agent chose an invalid move");
+        // The player to move now is the one that has to be told about it
+        match game.current_player {
+            Player::X => x.opponent_moved(move_, &game),
+            Player::O => o.opponent_moved(move_, &game),
+        }
+    }
+    game.get_winner()
+}
+
+/// How a [`play_match`] went for its first agent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchResult {
+    /// Games the first agent won
+    pub wins: usize,
+    /// Games that ended in a tie
+    pub ties: usize,
+    /// Games the first agent lost
+    pub losses: usize,
+}
+
+impl MatchResult {
+    /// The number of games played
+    pub fn games(&self) -> usize {
+        self.wins + self.ties + self.losses
+    }
+    /// The share of the points the first agent got, `1` per win and `0.5` per tie, from `0` to `1`
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.ties as f64 / 2.0) / self.games().max(1) as f64
+    }
+}
+
+/// Play `games` games from the start between a fresh agent from `first` and one from `second`
+/// each, alternating colours: the first agent is `X` in even games and `O` in odd ones.
+/// Both get the number of the game, for seeding
+pub fn play_match<A: Agent, B: Agent>(
+    games: usize,
+    mut first: impl FnMut(usize) -> A,
+    mut second: impl FnMut(usize) -> B,
+) -> MatchResult {
+    let mut result = MatchResult::default();
+    for i in 0..games {
+        let (mut a, mut b) = (first(i), second(i));
+        let (state, a_player) = if i % 2 == 0 {
+            (play_game(&mut a, &mut b, Game::new()), Player::X)
+        } else {
+            (play_game(&mut b, &mut a, Game::new()), Player::O)
+        };
+        match state {
+            GameState::Winner(player) if player == a_player => result.wins += 1,
+            GameState::Winner(_) => result.losses += 1,
+            _ => result.ties += 1,
+        }
+    }
+    result
+}
+
+/// Plays uniformly random legal moves
+#[derive(Debug, Clone)]
+pub struct RandomAgent {
+    rng: GameRng,
+}
+
+impl RandomAgent {
+    /// An agent that plays the same moves every time for the same `seed`
+    pub fn new(seed: u64) -> Self {
+        RandomAgent {
+            rng: rng::seeded(seed),
+        }
+    }
+}
+
+impl Default for RandomAgent {
+    /// An agent seeded by the operating system
+    fn default() -> Self {
+        RandomAgent {
+            rng: rng::from_entropy(),
+        }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn choose_move(&mut self, game: &Game) -> Move {
+        *game
+            .legal_moves()
+            .choose(&mut self.rng)
+            .expect("No valid moves")
+    }
+    fn name(&self) -> String {
+        "random".to_string()
+    }
+}
+
+/// Plays the move found by a [Monte Carlo Tree Search](crate::mcts)
+#[derive(Debug, Clone)]
+pub struct MctsAgent<P = Uniform> {
+    mcts: Mcts<P>,
+}
+
+impl MctsAgent {
+    /// Search according to `config` before every move
+    pub fn new(config: MctsConfig) -> Self {
+        MctsAgent {
+            mcts: Mcts::new(config),
+        }
+    }
+}
+
+impl<P: RolloutPolicy + Clone + Send + Sync> MctsAgent<P> {
+    /// Search according to `config` before every move, with playouts picked by `policy`
+    pub fn with_policy(config: MctsConfig, policy: P) -> Self {
+        MctsAgent {
+            mcts: Mcts::with_policy(config, policy),
+        }
+    }
+    /// The engine, to inspect its search tree
+    pub fn mcts(&self) -> &Mcts<P> {
+        &self.mcts
+    }
+}
+
+impl<P: RolloutPolicy + Clone + Send + Sync> Agent for MctsAgent<P> {
+    fn choose_move(&mut self, game: &Game) -> Move {
+        let move_ = self.mcts.search(*game);
+        if self.mcts.config().reuse_tree {
+            self.mcts.advance(move_);
+        }
+        move_
+    }
+    fn new_game(&mut self, _game: &Game) {
+        self.mcts.clear();
+    }
+    fn opponent_moved(&mut self, move_: Move, game: &Game) {
+        // Free the branches the opponent didn't take right away, if our tree is
+        // rooted where they moved from. Otherwise the next search sorts it out
+        let root_matches = self
+            .mcts
+            .root()
+            .and_then(|root| apply_move(move_, root).ok())
+            == Some(*game);
+        if self.mcts.config().reuse_tree && root_matches {
+            self.mcts.advance(move_);
+        }
+    }
+    fn name(&self) -> String {
+        "mcts".to_string()
+    }
+}
+
+/// Plays the move found by an [alpha-beta search](crate::alphabeta)
+#[derive(Debug, Clone)]
+pub struct AlphaBetaAgent<E = HandcraftedEvaluator> {
+    engine: AlphaBeta<E>,
+    last_search: Option<SearchInfo>,
+}
+
+impl AlphaBetaAgent {
+    /// Search within `limits` before every move, with the default [`HandcraftedEvaluator`]
+    pub fn new(limits: SearchLimits) -> Self {
+        Self::with_evaluator(limits, HandcraftedEvaluator::default())
+    }
+}
+
+impl<E: Evaluator> AlphaBetaAgent<E> {
+    /// Search within `limits` before every move, judging positions with `evaluator`
+    pub fn with_evaluator(limits: SearchLimits, evaluator: E) -> Self {
+        AlphaBetaAgent {
+            engine: AlphaBeta::with_evaluator(limits, evaluator),
+            last_search: None,
+        }
+    }
+    /// The depth, score and best line of the search for the last move
+    pub fn last_search(&self) -> Option<&SearchInfo> {
+        self.last_search.as_ref()
+    }
+}
+
+impl<E: Evaluator> Agent for AlphaBetaAgent<E> {
+    fn choose_move(&mut self, game: &Game) -> Move {
+        let info = self.engine.search(*game);
+        let move_ = info.best_move;
+        self.last_search = Some(info);
+        move_
+    }
+    fn new_game(&mut self, _game: &Game) {
+        self.engine.clear();
+        self.last_search = None;
+    }
+    fn name(&self) -> String {
+        "alphabeta".to_string()
+    }
+}
+
+/// Plays the move found by a [PUCT search](crate::puct), guided by a network or any other [`PolicyValue`]
+#[derive(Debug, Clone)]
+pub struct PuctAgent<E> {
+    puct: Puct<E>,
+}
+
+impl<E: PolicyValue> PuctAgent<E> {
+    /// Search according to `config` before every move, judging positions with `evaluator`
+    pub fn new(config: PuctConfig, evaluator: E) -> Self {
+        PuctAgent {
+            puct: Puct::new(config, evaluator),
+        }
+    }
+    /// The engine, to inspect its search tree
+    pub fn puct(&self) -> &Puct<E> {
+        &self.puct
+    }
+}
+
+impl<E: PolicyValue> Agent for PuctAgent<E> {
+    fn choose_move(&mut self, game: &Game) -> Move {
+        self.puct.search(*game)
+    }
+    fn name(&self) -> String {
+        "puct".to_string()
+    }
+}
+
+/// Plays moves from an [opening book](crate::book) while it has any, picking them at random in
+/// proportion to their weights, and lets another agent play the rest of the game
+#[derive(Debug, Clone)]
+pub struct BookAgent<A> {
+    book: Arc<Book>,
+    fallback: A,
+    rng: GameRng,
+}
+
+impl<A: Agent> BookAgent<A> {
+    /// Play from `book`, and let `fallback` take over once it runs out. The same `seed`
+    /// always picks the same book moves
+    pub fn new(book: Arc<Book>, fallback: A, seed: u64) -> Self {
+        BookAgent {
+            book,
+            fallback,
+            rng: rng::seeded(seed),
+        }
+    }
+    /// The book this agent plays from
+    pub fn book(&self) -> &Book {
+        &self.book
+    }
+    /// The agent that plays once the book runs out
+    pub fn fallback(&self) -> &A {
+        &self.fallback
+    }
+}
+
+impl<A: Agent> Agent for BookAgent<A> {
+    fn choose_move(&mut self, game: &Game) -> Move {
+        self.book
+            .choose(game, &mut self.rng)
+            .unwrap_or_else(|| self.fallback.choose_move(game))
+    }
+    fn new_game(&mut self, game: &Game) {
+        self.fallback.new_game(game)
+    }
+    fn opponent_moved(&mut self, move_: Move, game: &Game) {
+        self.fallback.opponent_moved(move_, game)
+    }
+    fn time_left(&mut self, remaining: Duration) {
+        self.fallback.time_left(remaining)
+    }
+    fn name(&self) -> String {
+        format!("book+{}", self.fallback.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agents_finish_games() {
+        let mut random = RandomAgent::new(0);
+        let mut mcts = MctsAgent::new(MctsConfig {
+            limits: SearchLimits::iterations(50),
+            seed: Some(0),
+            ..Default::default()
+        });
+        for _ in 0..3 {
+            let result = play_game(&mut random, &mut mcts, Game::new());
+            assert_ne!(result, GameState::InProgress);
+        }
+    }
+
+    #[test]
+    fn matches_alternate_colours() {
+        /// Always plays the first legal move
+        struct First;
+        impl Agent for First {
+            fn choose_move(&mut self, game: &Game) -> Move {
+                game.legal_moves()[0]
+            }
+            fn name(&self) -> String {
+                "first".to_string()
+            }
+        }
+        // The same two deterministic agents, so each colour always gets the same result
+        let result = play_match(2, |_| First, |_| First);
+        assert_eq!(result.games(), 2);
+        assert_eq!(result.wins, result.losses);
+        assert_eq!(result.score(), 0.5);
+    }
+
+    #[test]
+    fn book_agents_play_book_moves_first() {
+        let mut book = Book::new();
+        book.insert(&Game::new(), (1, 1, 1, 1), 1);
+        let mut agent = BookAgent::new(Arc::new(book), RandomAgent::new(0), 0);
+        let game = Game::new();
+        assert_eq!(agent.choose_move(&game), (1, 1, 1, 1));
+        // Out of book, the fallback plays
+        let game = apply_move((1, 1, 1, 1), game).unwrap();
+        assert!(game.legal_moves().contains(&agent.choose_move(&game)));
+    }
+}
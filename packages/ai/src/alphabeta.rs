@@ -0,0 +1,505 @@
+//! [Alpha-beta](https://www.chessprogramming.org/Alpha-Beta) search, the classic engine
+//! for tactical positions and a second opinion next to [MCTS](crate::mcts).
+//!
+//! The search is negamax with [principal variation search](https://www.chessprogramming.org/Principal_Variation_Search):
+//! the first move is searched with the full window and the rest with a null window,
+//! re-searching only the moves that turn out better. It deepens one ply at a time,
+//! and every iteration orders its moves with what the last one learned:
+//!
+//! 1. The *hash move*, the best move stored in the transposition table
+//! 2. Two *killer moves* per ply, quiet moves that caused a cutoff in a sibling position
+//! 3. Everything else by the *history heuristic*, how often a move caused cutoffs before
+//!
+//! Scores are from the perspective of the player to move. Forced wins score close to
+//! [`WIN`], less the number of plies until the win, so faster wins are preferred.
+//!
+//! Positions at the end of a line are judged by an [`Evaluator`], the
+//! [`HandcraftedEvaluator`] unless the engine is made [with another one](AlphaBeta::with_evaluator).
+//!
+//! For [`SearchLimits`], an iteration is one ply of depth, `nodes` counts the positions
+//! visited and `memory` is the size of the transposition table (16 MiB by default).
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use super_ttt::moves::MAX_MOVES;
+use super_ttt::{notation, Game, GameState, Move, GRID_SIZE};
+
+use crate::eval::{Evaluator, HandcraftedEvaluator};
+use crate::limits::SearchLimits;
+use crate::zobrist::{hash, play};
+use crate::{player_index, square_index};
+
+/// The score for winning on the spot. A win `n` plies away scores `WIN - n`
+pub const WIN: i32 = 1_000_000;
+const SQUARES: usize = GRID_SIZE * GRID_SIZE;
+/// A game can't last longer than this, so neither can a line of the search
+const MAX_PLY: usize = SQUARES + 1;
+/// Scores beyond this are forced wins or losses
+const WIN_THRESHOLD: i32 = WIN - MAX_PLY as i32;
+const INFINITY: i32 = WIN + 1;
+const DEFAULT_TABLE_BYTES: usize = 16 << 20;
+
+/// Wins are stored relative to the position rather than the root, so an entry
+/// doesn't depend on which root it was searched from
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    match score {
+        score if score >= WIN_THRESHOLD => score + ply as i32,
+        score if score <= -WIN_THRESHOLD => score - ply as i32,
+        score => score,
+    }
+}
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    match score {
+        score if score >= WIN_THRESHOLD => score - ply as i32,
+        score if score <= -WIN_THRESHOLD => score + ply as i32,
+        score => score,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Bound {
+    #[default]
+    Exact,
+    /// The score is at least this (the search failed high)
+    Lower,
+    /// The score is at most this (the search failed low)
+    Upper,
+}
+
+/// A transposition table entry. A key of `0` marks an empty slot
+#[derive(Debug, Clone, Copy, Default)]
+struct Entry {
+    key: u64,
+    score: i32,
+    depth: u8,
+    bound: Bound,
+    /// The best move as a square index
+    best: u8,
+}
+
+/// What a search found, at the deepest iteration it finished
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchInfo {
+    /// The move to play
+    pub best_move: Move,
+    /// The score of the position for the player to move. See the [module docs](self)
+    pub score: i32,
+    /// How many plies deep the search looked
+    pub depth: u32,
+    /// The principal variation: the best line for both sides, starting with [`SearchInfo::best_move`]
+    pub pv: Vec<Move>,
+    /// Positions visited in the whole search
+    pub nodes: u64,
+    /// How long the search took
+    pub time: Duration,
+}
+
+impl SearchInfo {
+    /// If the game is decided, the number of plies until the end: positive if the
+    /// player to move wins, negative if they lose
+    pub fn forced_win_in(&self) -> Option<i32> {
+        match self.score {
+            score if score >= WIN_THRESHOLD => Some(WIN - score),
+            score if score <= -WIN_THRESHOLD => Some(-(WIN + score)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SearchInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "depth {} score ", self.depth)?;
+        match self.forced_win_in() {
+            Some(plies) => write!(f, "win {plies}")?,
+            None => write!(f, "{}", self.score)?,
+        }
+        write!(f, " nodes {} time {:?} pv", self.nodes, self.time)?;
+        for &move_ in &self.pv {
+            write!(f, " {}", notation::move_to_string(move_))?;
+        }
+        Ok(())
+    }
+}
+
+/// A negamax alpha-beta engine. See the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct AlphaBeta<E = HandcraftedEvaluator> {
+    evaluator: E,
+    limits: SearchLimits,
+    table: Vec<Entry>,
+    killers: Vec<[Option<Move>; 2]>,
+    /// By player, then square index
+    history: [[u32; SQUARES]; 2],
+    nodes: u64,
+    start: Instant,
+    /// Whether the search may stop now, which it can't until it has a move
+    can_stop: bool,
+    stopped: bool,
+    root_best: Option<Move>,
+}
+
+impl AlphaBeta {
+    /// Create an engine that searches within `limits` with the default [`HandcraftedEvaluator`]
+    pub fn new(limits: SearchLimits) -> Self {
+        Self::with_evaluator(limits, HandcraftedEvaluator::default())
+    }
+}
+
+impl<E: Evaluator> AlphaBeta<E> {
+    /// Create an engine that searches within `limits` and judges positions with `evaluator`
+    pub fn with_evaluator(limits: SearchLimits, evaluator: E) -> Self {
+        let table_bytes = limits.memory.unwrap_or(DEFAULT_TABLE_BYTES);
+        // A power of two, so the index is just the low bits of the hash
+        let entries = (table_bytes / std::mem::size_of::<Entry>()).max(2);
+        let entries = 1 << entries.ilog2();
+        AlphaBeta {
+            evaluator,
+            limits,
+            table: vec![Entry::default(); entries],
+            killers: vec![[None; 2]; MAX_PLY],
+            history: [[0; SQUARES]; 2],
+            nodes: 0,
+            start: Instant::now(),
+            can_stop: false,
+            stopped: false,
+            root_best: None,
+        }
+    }
+    /// The evaluator this engine judges positions with
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+    /// The limits this engine searches within
+    pub fn limits(&self) -> &SearchLimits {
+        &self.limits
+    }
+    /// Forget everything learned in earlier searches
+    pub fn clear(&mut self) {
+        self.table.fill(Entry::default());
+        self.killers.fill([None; 2]);
+        self.history = [[0; SQUARES]; 2];
+    }
+    /// Search `game` until one of the limits is reached or the result is certain
+    ///
+    /// # Panics
+    /// Panics if the game is already over or the search has no time, iteration or node limit.
+    /// A `memory` limit only sizes the transposition table, so it can't stop a search
+    pub fn search(&mut self, game: Game) -> SearchInfo {
+        self.search_with(game, |_| {})
+    }
+    /// Like [`AlphaBeta::search`], calling `on_depth` after every finished iteration
+    pub fn search_with(&mut self, game: Game, mut on_depth: impl FnMut(&SearchInfo)) -> SearchInfo {
+        assert_eq!(
+            game.get_winner(),
+            GameState::InProgress,
+            "can't search a finished game"
+        );
+        let SearchLimits {
+            time,
+            iterations,
+            nodes,
+            ..
+        } = self.limits;
+        assert!(
+            time.is_some() || iterations.is_some() || nodes.is_some(),
+            "the search would never stop"
+        );
+        self.start = Instant::now();
+        self.nodes = 0;
+        self.can_stop = false;
+        self.stopped = false;
+        // Old history is less relevant, but still better than nothing
+        self.history
+            .iter_mut()
+            .flatten()
+            .for_each(|count| *count /= 8);
+
+        let root_hash = hash(&game);
+        let empty_squares = game
+            .iter_squares()
+            .filter(|&(_, square)| square == super_ttt::Square::Empty)
+            .count();
+        let max_depth = self
+            .limits
+            .iterations
+            .unwrap_or(MAX_PLY)
+            .clamp(1, empty_squares);
+        let mut best: Option<SearchInfo> = None;
+        for depth in 1..=max_depth as u32 {
+            self.root_best = None;
+            let score = self.negamax(&game, root_hash, depth, 0, -INFINITY, INFINITY);
+            if self.stopped {
+                // Half an iteration can't be trusted
+                break;
+            }
+            let best_move = self.root_best.expect("the root has legal moves");
+            let info = SearchInfo {
+                best_move,
+                score,
+                depth,
+                pv: self.principal_variation(game, root_hash, best_move, depth),
+                nodes: self.nodes,
+                time: self.start.elapsed(),
+            };
+            on_depth(&info);
+            best = Some(info);
+            self.can_stop = true;
+            if score.abs() >= WIN_THRESHOLD {
+                break;
+            }
+        }
+        let mut best = best.expect("the first iteration always finishes");
+        best.nodes = self.nodes;
+        best.time = self.start.elapsed();
+        best
+    }
+
+    fn negamax(
+        &mut self,
+        game: &Game,
+        hash: u64,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.can_stop && !self.stopped {
+            let out_of_nodes = self.limits.nodes.is_some_and(|n| self.nodes >= n as u64);
+            // Looking at the clock every node would slow things down.
+            // `u64::is_multiple_of` would need Rust 1.87
+            #[allow(clippy::manual_is_multiple_of)]
+            let out_of_time = self.nodes % 1024 == 0
+                && self.limits.time.is_some_and(|t| self.start.elapsed() >= t);
+            self.stopped = out_of_nodes || out_of_time;
+        }
+        if self.stopped {
+            return 0;
+        }
+        match game.get_winner() {
+            // The player who just moved won
+            GameState::Winner(_) => return -(WIN - ply as i32),
+            GameState::Tie => return 0,
+            GameState::InProgress => {}
+        }
+        if depth == 0 {
+            // Heuristics can't outweigh a forced win
+            return self
+                .evaluator
+                .evaluate(game)
+                .clamp(1 - WIN_THRESHOLD, WIN_THRESHOLD - 1);
+        }
+
+        let slot = hash as usize & (self.table.len() - 1);
+        let entry = self.table[slot];
+        let mut hash_move = None;
+        if entry.key == hash {
+            hash_move = Some(super_ttt::from_square_coords(
+                entry.best as usize / GRID_SIZE,
+                entry.best as usize % GRID_SIZE,
+            ));
+            let score = score_from_table(entry.score, ply);
+            // Always search the root, so there's a best move
+            if ply > 0 && entry.depth as u32 >= depth {
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+        }
+
+        let player = player_index(game.current_player);
+        let legal = game.legal_moves();
+        let mut ordered = [(0, (0, 0, 0, 0)); MAX_MOVES];
+        for (slot, &move_) in ordered.iter_mut().zip(&legal) {
+            let order = if Some(move_) == hash_move {
+                u32::MAX
+            } else if Some(move_) == self.killers[ply][0] {
+                u32::MAX - 1
+            } else if Some(move_) == self.killers[ply][1] {
+                u32::MAX - 2
+            } else {
+                self.history[player][square_index(move_)].min(u32::MAX - 3)
+            };
+            *slot = (order, move_);
+        }
+        let moves = &mut ordered[..legal.len()];
+        moves.sort_unstable_by_key(|&(order, _)| std::cmp::Reverse(order));
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = moves[0].1;
+        for (i, &(_, move_)) in moves.iter().enumerate() {
+            let (child, child_hash) = play(game, hash, move_);
+            let score = if i == 0 {
+                -self.negamax(&child, child_hash, depth - 1, ply + 1, -beta, -alpha)
+            } else {
+                let score =
+                    -self.negamax(&child, child_hash, depth - 1, ply + 1, -alpha - 1, -alpha);
+                if score > alpha && score < beta {
+                    -self.negamax(&child, child_hash, depth - 1, ply + 1, -beta, -alpha)
+                } else {
+                    score
+                }
+            };
+            if self.stopped {
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = move_;
+                if ply == 0 {
+                    self.root_best = Some(move_);
+                }
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                if Some(move_) != hash_move && self.killers[ply][0] != Some(move_) {
+                    self.killers[ply] = [Some(move_), self.killers[ply][0]];
+                }
+                let count = &mut self.history[player][square_index(move_)];
+                *count = count.saturating_add(depth * depth);
+                break;
+            }
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score <= original_alpha {
+            Bound::Upper
+        } else {
+            Bound::Exact
+        };
+        self.table[slot] = Entry {
+            key: hash,
+            score: score_to_table(best_score, ply),
+            depth: depth as u8,
+            bound,
+            best: square_index(best_move) as u8,
+        };
+        best_score
+    }
+
+    /// Follow the hash moves from the root for up to `depth` plies
+    fn principal_variation(
+        &self,
+        mut game: Game,
+        mut hash: u64,
+        best: Move,
+        depth: u32,
+    ) -> Vec<Move> {
+        let mut pv = vec![best];
+        (game, hash) = play(&game, hash, best);
+        while pv.len() < depth as usize && game.get_winner() == GameState::InProgress {
+            let entry = self.table[hash as usize & (self.table.len() - 1)];
+            if entry.key != hash {
+                break;
+            }
+            let move_ = super_ttt::from_square_coords(
+                entry.best as usize / GRID_SIZE,
+                entry.best as usize % GRID_SIZE,
+            );
+            if !game.legal_moves().contains(&move_) {
+                break;
+            }
+            pv.push(move_);
+            (game, hash) = play(&game, hash, move_);
+        }
+        pv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{play_match, AlphaBetaAgent, RandomAgent};
+    use crate::apply_move;
+    use crate::test_positions::{immediate_win, late_positions};
+
+    /// Plain negamax without any pruning, to check the engine against
+    fn minimax(game: &Game, depth: u32, ply: usize) -> i32 {
+        match game.get_winner() {
+            GameState::Winner(_) => return -(WIN - ply as i32),
+            GameState::Tie => return 0,
+            GameState::InProgress => {}
+        }
+        if depth == 0 {
+            return HandcraftedEvaluator::default().evaluate(game);
+        }
+        game.legal_moves()
+            .iter()
+            .map(|&move_| -minimax(&apply_move(move_, *game).unwrap(), depth - 1, ply + 1))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn takes_an_immediate_win() {
+        let game = immediate_win();
+        let info = AlphaBeta::new(SearchLimits::iterations(4)).search(game);
+        assert_eq!(info.best_move, (0, 2, 0, 2));
+        assert_eq!(info.forced_win_in(), Some(1));
+        assert_eq!(info.pv, [(0, 2, 0, 2)]);
+    }
+
+    #[test]
+    fn agrees_with_minimax() {
+        for game in late_positions(12) {
+            let mut engine = AlphaBeta::new(SearchLimits::iterations(3));
+            let info = engine.search(game);
+            // Every move adds a piece, so a position can only come up again at the same ply
+            // and the transposition table never mixes in results of other depths
+            assert_eq!(
+                info.score,
+                minimax(&game, info.depth, 0),
+                "{}",
+                game.to_position_string()
+            );
+            let mut replay = game;
+            for &move_ in &info.pv {
+                replay = apply_move(move_, replay).expect("the PV is legal");
+            }
+        }
+    }
+
+    #[test]
+    fn stops_at_the_limits() {
+        let limits = SearchLimits {
+            nodes: Some(5_000),
+            ..Default::default()
+        };
+        let info = AlphaBeta::new(limits).search(Game::new());
+        // The first iteration (82 nodes) always finishes, the rest stop right at the limit
+        assert_eq!(info.nodes, 5_000);
+        assert!(info.depth >= 2);
+
+        let mut seen = Vec::new();
+        AlphaBeta::new(SearchLimits::iterations(3)).search_with(Game::new(), |info| {
+            seen.push(info.depth);
+        });
+        assert_eq!(seen, [1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "never stop")]
+    fn needs_a_limit_besides_memory() {
+        let limits = SearchLimits {
+            memory: Some(1 << 20),
+            ..Default::default()
+        };
+        AlphaBeta::new(limits).search(Game::new());
+    }
+
+    #[test]
+    fn beats_random() {
+        let wins = play_match(
+            4,
+            |_| AlphaBetaAgent::new(SearchLimits::iterations(3)),
+            |i| RandomAgent::new(i as u64),
+        )
+        .wins;
+        assert!(wins >= 3, "alpha-beta only won {wins} of 4 games");
+    }
+}
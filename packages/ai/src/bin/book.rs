@@ -0,0 +1,135 @@
+//! Build and inspect opening books.
+//!
+//! ```text
+//! book build <book> [depth] [positions] [mcts iterations] [seed]
+//! book show <book> [position string]
+//! ```
+//!
+//! `build` grows the book with MCTS searches, saving it as it goes so it can be stopped and
+//! picked up again later: if the file already exists, the positions in it aren't searched again.
+//! `show` prints the book moves for a position (the starting position by default) and how often
+//! each one is played.
+use std::path::Path;
+use std::process::exit;
+
+use ai::book::{Book, BookBuilder, BookConfig, BookSearch};
+use ai::limits::SearchLimits;
+use ai::mcts::MctsConfig;
+use ai::rng::random_seed;
+use super_ttt::notation::move_to_string;
+use super_ttt::Game;
+
+const USAGE: &str = "usage: book build <book> [depth] [positions] [mcts iterations] [seed]
+       book show <book> [position string]";
+/// How many positions are searched between saves
+const SAVE_EVERY: usize = 10;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["build", book, ref rest @ ..] if rest.len() <= 4 => build(book, rest),
+        ["show", book] => show(book, None),
+        ["show", book, position] => show(book, Some(position)),
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(err) = result {
+        eprintln!("{err}");
+        exit(1);
+    }
+}
+
+fn number(arg: Option<&&str>, default: usize) -> Result<usize, String> {
+    arg.map_or(Ok(default), |arg| {
+        arg.parse().map_err(|_| format!("{arg:?} isn't a number"))
+    })
+}
+
+fn load(path: &str) -> Result<Book, String> {
+    Book::load(path).map_err(|err| format!("couldn't read {path}: {err}"))
+}
+
+fn build(path: &str, args: &[&str]) -> Result<(), String> {
+    let defaults = BookConfig::default();
+    let depth = number(args.first(), defaults.depth)?;
+    let positions = number(args.get(1), defaults.positions)?;
+    let iterations = number(args.get(2), 20_000)?;
+    let seed = match args.get(3) {
+        Some(seed) => seed.parse().map_err(|_| format!("{seed:?} isn't a seed"))?,
+        None => random_seed(),
+    };
+    println!("Seed {seed}");
+    let mut book = if Path::new(path).exists() {
+        load(path)?
+    } else {
+        Book::new()
+    };
+    println!("Starting with {} positions", book.len());
+
+    let config = BookConfig {
+        search: BookSearch::Mcts(MctsConfig {
+            limits: SearchLimits::iterations(iterations),
+            seed: Some(seed),
+            ..Default::default()
+        }),
+        depth,
+        ..defaults
+    };
+    let mut searched = 0;
+    // Grow a few positions at a time, saving in between
+    while searched < positions {
+        let batch = SAVE_EVERY.min(positions - searched);
+        let mut builder = BookBuilder::with_book(
+            book,
+            BookConfig {
+                positions: batch,
+                ..config
+            },
+        );
+        let grown = builder.grow(Game::new(), |game, moves| {
+            let moves: Vec<_> = moves
+                .iter()
+                .map(|book_move| {
+                    format!("{} ({})", move_to_string(book_move.move_), book_move.weight)
+                })
+                .collect();
+            println!("{}: {}", game.to_position_string(), moves.join(", "));
+        });
+        book = builder.into_book();
+        book.save(path)
+            .map_err(|err| format!("couldn't write {path}: {err}"))?;
+        searched += grown;
+        if grown < batch {
+            // Everything up to the depth is in the book
+            break;
+        }
+    }
+    println!(
+        "Searched {searched} positions, the book now has {}",
+        book.len()
+    );
+    Ok(())
+}
+
+fn show(path: &str, position: Option<&str>) -> Result<(), String> {
+    let book = load(path)?;
+    let game = match position {
+        Some(position) => Game::from_position_string(position)
+            .map_err(|err| format!("{position:?} isn't a position: {err}"))?,
+        None => Game::new(),
+    };
+    println!("{game}");
+    let mut moves = book.moves(&game);
+    if moves.is_empty() {
+        println!("Not in the book");
+    }
+    moves.sort_by_key(|book_move| std::cmp::Reverse(book_move.weight));
+    let total: u64 = moves.iter().map(|book_move| book_move.weight as u64).sum();
+    for book_move in moves {
+        println!(
+            "{:<4} {:>5.1}%",
+            move_to_string(book_move.move_),
+            100.0 * book_move.weight as f64 / total.max(1) as f64
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+//! Generate training data for a network by self-play.
+//!
+//! ```text
+//! selfplay <dataset> [games] [iterations] [seed] [network]
+//! ```
+//!
+//! Plays games of PUCT against itself in parallel, with the settings of
+//! [`SelfPlayConfig::default`] and `iterations` iterations a move (400 by default), and writes
+//! every position to the dataset (see [`ai::selfplay`] for the format). Positions are judged by
+//! the network in the `network` file, or by random playouts without one.
+//!
+//! If the dataset already exists, the run picks up where it stopped: it keeps the seed the file
+//! was started with and only plays the games that are missing to get to `games`. The same seed
+//! and settings always give the same dataset, however many threads play it and however often it's
+//! interrupted.
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Seek, SeekFrom};
+use std::path::Path;
+use std::process::exit;
+
+use ai::limits::SearchLimits;
+use ai::nn::Network;
+use ai::puct::{PolicyValue, PuctConfig, Rollouts};
+use ai::rng::{random_seed, task_rng};
+use ai::selfplay::{self_play, DatasetReader, DatasetWriter, Record, SelfPlayConfig};
+use rayon::prelude::*;
+use super_ttt::Game;
+
+const USAGE: &str = "usage: selfplay <dataset> [games] [iterations] [seed] [network]";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [dataset, ref rest @ ..] if rest.len() <= 4 && !dataset.starts_with('-') => {
+            run(dataset, rest)
+        }
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(err) = result {
+        eprintln!("{err}");
+        exit(1);
+    }
+}
+
+fn number(arg: Option<&&str>, default: usize) -> Result<usize, String> {
+    arg.map_or(Ok(default), |arg| {
+        arg.parse().map_err(|_| format!("{arg:?} isn't a number"))
+    })
+}
+
+/// Open an existing dataset to add games to it, dropping a game that was only partly written.
+/// Returns the seed it was started with, the number of games in it and a writer at its end
+fn resume(path: &str) -> io::Result<(u64, usize, DatasetWriter<BufWriter<File>>)> {
+    let mut reader = DatasetReader::new(BufReader::new(File::open(path)?))?;
+    let mut games = 0;
+    loop {
+        match reader.read_game() {
+            Ok(Some(_)) => games += 1,
+            Ok(None) => break,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err),
+        }
+    }
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.set_len(reader.complete_len())?;
+    file.seek(SeekFrom::End(0))?;
+    Ok((
+        reader.seed(),
+        games,
+        DatasetWriter::append(BufWriter::new(file)),
+    ))
+}
+
+fn run(path: &str, args: &[&str]) -> Result<(), String> {
+    let games = number(args.first(), 100)?;
+    let iterations = number(args.get(1), 400)?;
+    let seed = match args.get(2) {
+        Some(seed) => Some(seed.parse().map_err(|_| format!("{seed:?} isn't a seed"))?),
+        None => None,
+    };
+    let evaluator: Box<dyn PolicyValue + Send + Sync> = match args.get(3) {
+        Some(network) => Box::new(
+            Network::load(network).map_err(|err| format!("couldn't read {network}: {err}"))?,
+        ),
+        None => Box::new(Rollouts),
+    };
+
+    let (seed, done, mut writer) = if Path::new(path).exists() {
+        let (started_with, done, writer) =
+            resume(path).map_err(|err| format!("couldn't continue {path}: {err}"))?;
+        if seed.is_some_and(|seed| seed != started_with) {
+            return Err(format!("{path} was started with seed {started_with}"));
+        }
+        (started_with, done, writer)
+    } else {
+        let seed = seed.unwrap_or_else(random_seed);
+        let file = File::create(path).map_err(|err| format!("couldn't create {path}: {err}"))?;
+        // Write the header out straight away, so that a run stopped before its first batch
+        // still leaves a dataset that can be continued
+        let writer = DatasetWriter::new(BufWriter::new(file), seed)
+            .and_then(|mut writer| writer.flush().map(|()| writer))
+            .map_err(|err| format!("couldn't write {path}: {err}"))?;
+        (seed, 0, writer)
+    };
+    println!("Seed {seed}, {done} games already played");
+
+    let defaults = SelfPlayConfig::default();
+    let config = SelfPlayConfig {
+        search: PuctConfig {
+            limits: SearchLimits::iterations(iterations),
+            ..defaults.search
+        },
+        ..defaults
+    };
+    // Play a few games per thread at a time, and write them in order
+    let batch = rayon::current_num_threads() * 4;
+    let mut positions = 0;
+    let mut next = done;
+    while next < games {
+        let end = games.min(next + batch);
+        let played: Vec<Vec<Record>> = (next..end)
+            .into_par_iter()
+            .map(|game| {
+                self_play(
+                    &evaluator,
+                    &config,
+                    Game::new(),
+                    &mut task_rng(seed, game as u64),
+                )
+            })
+            .collect();
+        for game in &played {
+            writer
+                .write_game(game)
+                .map_err(|err| format!("couldn't write {path}: {err}"))?;
+            positions += game.len();
+        }
+        writer
+            .flush()
+            .map_err(|err| format!("couldn't write {path}: {err}"))?;
+        next = end;
+        eprint!("\r{next}/{games} games");
+    }
+    eprintln!();
+    println!("Wrote {positions} positions");
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Tune the weights of the handcrafted evaluator on a corpus of labelled positions.
+//!
+//! ```text
+//! tune generate <corpus> [games] [mcts iterations] [seed]
+//! tune fit <corpus> <weights> [epochs] [starting weights]
+//! ```
+//!
+//! `generate` plays games and writes every position with its result to the corpus. By default the
+//! games are random playouts; with a number of MCTS iterations, MCTS plays itself instead.
+//! The same seed always generates the same corpus.
+//! `fit` fits the weights to the corpus, printing the error as it goes, and writes them to a file.
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::process::exit;
+
+use ai::agent::MctsAgent;
+use ai::eval::{Weights, FEATURE_NAMES};
+use ai::limits::SearchLimits;
+use ai::mcts::MctsConfig;
+use ai::playout::playout_with;
+use ai::rng::{random_seed, task_rng};
+use ai::tuning::{read_corpus, record_game, write_corpus, Sample, Tuner};
+use rand::Rng;
+use super_ttt::Game;
+
+const USAGE: &str = "usage: tune generate <corpus> [games] [mcts iterations] [seed]
+       tune fit <corpus> <weights> [epochs] [starting weights]";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["generate", corpus, ref rest @ ..] if rest.len() <= 3 => generate(corpus, rest),
+        ["fit", corpus, weights, ref rest @ ..] if rest.len() <= 2 => fit(corpus, weights, rest),
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(err) = result {
+        eprintln!("{err}");
+        exit(1);
+    }
+}
+
+fn number(arg: Option<&&str>, default: usize) -> Result<usize, String> {
+    arg.map_or(Ok(default), |arg| {
+        arg.parse().map_err(|_| format!("{arg:?} isn't a number"))
+    })
+}
+
+fn generate(corpus: &str, args: &[&str]) -> Result<(), String> {
+    let games = number(args.first(), 1_000)?;
+    let iterations = number(args.get(1), 0)?;
+    let seed = match args.get(2) {
+        Some(seed) => seed.parse().map_err(|_| format!("{seed:?} isn't a seed"))?,
+        None => random_seed(),
+    };
+    println!("Seed {seed}");
+    let mut samples: Vec<Sample> = Vec::new();
+    for i in 0..games {
+        let mut rng = task_rng(seed, i as u64);
+        if iterations == 0 {
+            let mut positions = Vec::new();
+            let result =
+                playout_with(Game::new(), &mut rng, |game, _| positions.push(*game)).result;
+            samples.extend(positions.into_iter().map(|game| Sample { game, result }));
+        } else {
+            let config = |seed| MctsConfig {
+                limits: SearchLimits::iterations(iterations),
+                seed: Some(seed),
+                ..Default::default()
+            };
+            let mut x = MctsAgent::new(config(rng.gen()));
+            let mut o = MctsAgent::new(config(rng.gen()));
+            samples.extend(record_game(&mut x, &mut o, Game::new()));
+            eprint!("\r{}/{games} games", i + 1);
+        }
+    }
+    eprintln!();
+    let file = File::create(corpus).map_err(|err| format!("couldn't create {corpus}: {err}"))?;
+    write_corpus(BufWriter::new(file), &samples)
+        .map_err(|err| format!("couldn't write {corpus}: {err}"))?;
+    println!(
+        "Wrote {} positions from {games} games to {corpus}",
+        samples.len()
+    );
+    Ok(())
+}
+
+fn fit(corpus: &str, weights_path: &str, args: &[&str]) -> Result<(), String> {
+    let epochs = number(args.first(), 300)?;
+    let start = match args.get(1) {
+        Some(path) => Weights::load(path).map_err(|err| format!("{path}: {err}"))?,
+        None => Weights::default(),
+    };
+    let file = File::open(corpus).map_err(|err| format!("couldn't open {corpus}: {err}"))?;
+    let samples = read_corpus(BufReader::new(file)).map_err(|err| format!("{corpus}: {err}"))?;
+    let mut tuner = Tuner::new(&samples);
+    if tuner.is_empty() {
+        return Err(format!("{corpus} has no positions to tune on"));
+    }
+
+    let scale = tuner.fit_scale(&start);
+    println!("{} positions, scale {scale:.6}", tuner.len());
+    let mut errors = vec![tuner.error(&start)];
+    let weights = tuner.fit(start, epochs, |_, error| errors.push(error));
+
+    // About 20 points of the curve, with bars from the lowest error to the highest
+    let (lowest, highest) = (errors[errors.len() - 1].min(errors[0]), errors[0]);
+    let every = (epochs / 20).max(1);
+    println!("epoch     error");
+    for (epoch, error) in errors.iter().enumerate() {
+        if epoch % every == 0 || epoch == epochs {
+            let bar = 40.0 * (error - lowest) / (highest - lowest).max(f64::EPSILON);
+            println!(
+                "{epoch:>5}  {error:.6}  {}",
+                "#".repeat(bar.round() as usize)
+            );
+        }
+    }
+    println!();
+    for (name, (before, after)) in FEATURE_NAMES.iter().zip(start.0.iter().zip(weights.0)) {
+        println!("{name:<15} {before:>9.2} -> {after:>9.2}");
+    }
+    weights
+        .save(weights_path)
+        .map_err(|err| format!("couldn't write {weights_path}: {err}"))?;
+    println!("Wrote the weights to {weights_path}");
+    Ok(())
+}
@@ -0,0 +1,422 @@
+//! Opening books: moves worked out ahead of time, so engines don't spend their budget thinking
+//! about the same first few moves every game.
+//!
+//! A [`BookBuilder`] grows a [`Book`] breadth-first from the starting position (or any other),
+//! searching every position with deep MCTS or alpha-beta searches and following the moves it
+//! finds. Positions are stored by their [canonical form](crate::symmetry::canonical), so a
+//! position and all of its rotations and reflections share one entry, and so do equivalent
+//! moves in a symmetric position. Every move has a weight (its visits, for MCTS), and
+//! [`Book::choose`] picks moves in proportion to them, so a
+//! [`BookAgent`](crate::agent::BookAgent) doesn't play the same opening every game.
+//!
+//! # File format
+//!
+//! A book file is the 8 byte [`FILE_HEADER`] followed by one entry per position, sorted by key.
+//! All numbers are little-endian:
+//!
+//! - 8 bytes: the [Zobrist hash](https://www.chessprogramming.org/Zobrist_Hashing) of the canonical position
+//! - 1 byte: the number of moves, `1` to `81`
+//! - 5 bytes per move: its [square coordinate](super_ttt::to_square_coords) `row * 9 + col`
+//!   in the canonical position, then its weight as 4 bytes
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use super_ttt::binary::read_record;
+use super_ttt::{from_square_coords, Game, GameState, Move, GRID_SIZE};
+
+use crate::alphabeta::AlphaBeta;
+use crate::limits::SearchLimits;
+use crate::mcts::{Mcts, MctsConfig, Proof};
+use crate::symmetry::{canonical, Symmetry};
+use crate::zobrist::hash;
+use crate::{apply_move, square_index};
+
+/// The first bytes of every book file. The last byte is the format version
+pub const FILE_HEADER: [u8; 8] = *b"STTTBOK\x01";
+
+/// A move in a [`Book`] and how often it should be played, relative to the other moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookMove {
+    /// The move
+    pub move_: Move,
+    /// Its weight. Moves with a weight of `0` are never chosen
+    pub weight: u32,
+}
+
+/// Book moves for positions, folded by symmetry. See the [module docs](self)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Book {
+    /// Moves in the orientation of the canonical position, by its hash
+    entries: HashMap<u64, Vec<BookMove>>,
+}
+
+impl Book {
+    /// An empty book
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The number of positions in the book
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Whether the book has no positions
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Whether the book has moves for `game` (or a rotation or reflection of it)
+    pub fn contains(&self, game: &Game) -> bool {
+        self.entries.contains_key(&key(game).0)
+    }
+    /// Add `weight` to `move_` in `game`, adding the move (and the position) if it isn't in the book yet
+    pub fn insert(&mut self, game: &Game, move_: Move, weight: u32) {
+        let (key, symmetry, representative) = key(game);
+        let move_ = fold(&representative, symmetry.move_(move_));
+        let moves = self.entries.entry(key).or_default();
+        match moves.iter_mut().find(|book_move| book_move.move_ == move_) {
+            Some(book_move) => book_move.weight = book_move.weight.saturating_add(weight),
+            None => moves.push(BookMove { move_, weight }),
+        }
+    }
+    /// The book moves for `game`, turned to fit it. Empty if `game` isn't in the book
+    pub fn moves(&self, game: &Game) -> Vec<BookMove> {
+        let (key, symmetry, _) = key(game);
+        let back = symmetry.inverse();
+        let legal = game.legal_moves();
+        self.entries
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .map(|&BookMove { move_, weight }| BookMove {
+                move_: back.move_(move_),
+                weight,
+            })
+            // Only a hash collision could make a move illegal, but a book is no reason to crash
+            .filter(|book_move| legal.contains(&book_move.move_))
+            .collect()
+    }
+    /// A random book move for `game`, picked in proportion to the weights,
+    /// or `None` if the book has nothing to play
+    pub fn choose(&self, game: &Game, rng: &mut impl Rng) -> Option<Move> {
+        self.moves(game)
+            .choose_weighted(rng, |book_move| book_move.weight)
+            .ok()
+            .map(|book_move| book_move.move_)
+    }
+
+    /// Write the book in the [file format](self#file-format)
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(&FILE_HEADER)?;
+        let mut keys: Vec<_> = self.entries.keys().collect();
+        keys.sort_unstable();
+        for key in keys {
+            let moves = &self.entries[key];
+            if moves.is_empty() {
+                continue;
+            }
+            writer.write_all(&key.to_le_bytes())?;
+            writer.write_all(&[moves.len() as u8])?;
+            for book_move in moves {
+                writer.write_all(&[square_index(book_move.move_) as u8])?;
+                writer.write_all(&book_move.weight.to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+    /// Read a book in the [file format](self#file-format). A file that doesn't follow it is an
+    /// [`InvalidData`](io::ErrorKind::InvalidData) error, and one that ends partway through
+    /// an entry is an [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) error
+    pub fn read(mut reader: impl Read) -> io::Result<Self> {
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut header = [0; FILE_HEADER.len()];
+        reader.read_exact(&mut header)?;
+        if header != FILE_HEADER {
+            return Err(invalid("not a super tic tac toe opening book"));
+        }
+        let mut book = Book::new();
+        let mut key = [0; 8];
+        loop {
+            // The end of the file is only fine between entries
+            if !read_record(&mut reader, &mut key)? {
+                break;
+            }
+            let mut count = [0];
+            reader.read_exact(&mut count)?;
+            if !(1..=GRID_SIZE * GRID_SIZE).contains(&(count[0] as usize)) {
+                return Err(invalid("a book entry has an invalid number of moves"));
+            }
+            let mut moves = Vec::with_capacity(count[0] as usize);
+            for _ in 0..count[0] {
+                let mut bytes = [0; 5];
+                reader.read_exact(&mut bytes)?;
+                let square = bytes[0] as usize;
+                if square >= GRID_SIZE * GRID_SIZE {
+                    return Err(invalid("a book move is off the board"));
+                }
+                moves.push(BookMove {
+                    move_: from_square_coords(square / GRID_SIZE, square % GRID_SIZE),
+                    weight: u32::from_le_bytes(bytes[1..].try_into().unwrap()),
+                });
+            }
+            book.entries.insert(u64::from_le_bytes(key), moves);
+        }
+        Ok(book)
+    }
+    /// Write the book to a file that [`Book::load`] can read
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write(BufWriter::new(std::fs::File::create(path)?))
+    }
+    /// Read a book from a file
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read(BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+/// The hash of the canonical form of `game`, the symmetry that turns `game` into it, and the canonical form
+fn key(game: &Game) -> (u64, Symmetry, Game) {
+    let (representative, symmetry) = canonical(game);
+    (hash(&representative), symmetry, representative)
+}
+/// The same move for every move that's equivalent to `move_` in the symmetric position `game`
+fn fold(game: &Game, move_: Move) -> Move {
+    Symmetry::ALL
+        .into_iter()
+        .filter(|symmetry| symmetry.game(game) == *game)
+        .map(|symmetry| symmetry.move_(move_))
+        .min_by_key(|&move_| square_index(move_))
+        .expect("the identity leaves every position as it is")
+}
+
+/// The searches a [`BookBuilder`] works out book moves with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BookSearch {
+    /// Search with MCTS, keeping every move visited often enough
+    /// (see [`BookConfig::min_share`]) with its visits as the weight
+    Mcts(MctsConfig),
+    /// Search with alpha-beta within these limits. Only its best move is kept, with a weight of `1`
+    AlphaBeta(SearchLimits),
+}
+
+/// How a [`BookBuilder`] grows a book
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookConfig {
+    /// How every position is searched
+    pub search: BookSearch,
+    /// How many plies from the root the book reaches
+    pub depth: usize,
+    /// The most positions to search
+    pub positions: usize,
+    /// With [`BookSearch::Mcts`], the fewest visits a move can have to make it into the book,
+    /// as a share of the visits of the most visited move. Lower values give wider books
+    pub min_share: f64,
+}
+
+impl Default for BookConfig {
+    fn default() -> Self {
+        BookConfig {
+            search: BookSearch::Mcts(MctsConfig {
+                limits: SearchLimits::iterations(20_000),
+                ..Default::default()
+            }),
+            depth: 4,
+            positions: 1_000,
+            min_share: 0.25,
+        }
+    }
+}
+
+/// Grows a [`Book`]. See the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct BookBuilder {
+    config: BookConfig,
+    book: Book,
+}
+
+impl BookBuilder {
+    /// Start a new book
+    pub fn new(config: BookConfig) -> Self {
+        Self::with_book(Book::new(), config)
+    }
+    /// Keep growing `book`. Positions it already has aren't searched again
+    pub fn with_book(book: Book, config: BookConfig) -> Self {
+        BookBuilder { config, book }
+    }
+    /// How the book is grown
+    pub fn config(&self) -> &BookConfig {
+        &self.config
+    }
+    /// The book so far
+    pub fn book(&self) -> &Book {
+        &self.book
+    }
+    /// The finished book
+    pub fn into_book(self) -> Book {
+        self.book
+    }
+    /// Search every position up to [`BookConfig::depth`] plies from `root` that can be reached
+    /// by book moves, closest positions first, until [`BookConfig::positions`] have been searched.
+    /// `progress` is called with every searched position and the moves found for it.
+    /// Returns the number of positions searched
+    pub fn grow(&mut self, root: Game, mut progress: impl FnMut(&Game, &[BookMove])) -> usize {
+        let mut searched = 0;
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([(root, 0)]);
+        while let Some((game, ply)) = queue.pop_front() {
+            if ply >= self.config.depth
+                || game.get_winner() != GameState::InProgress
+                || !seen.insert(key(&game).0)
+            {
+                continue;
+            }
+            if !self.book.contains(&game) {
+                if searched == self.config.positions {
+                    break;
+                }
+                let moves = self.search(game);
+                for book_move in &moves {
+                    self.book.insert(&game, book_move.move_, book_move.weight);
+                }
+                searched += 1;
+                progress(&game, &moves);
+            }
+            for book_move in self.book.moves(&game) {
+                let child = apply_move(book_move.move_, game).expect("book moves are legal");
+                queue.push_back((child, ply + 1));
+            }
+        }
+        searched
+    }
+
+    fn search(&self, game: Game) -> Vec<BookMove> {
+        match self.config.search {
+            BookSearch::Mcts(config) => {
+                let mut mcts = Mcts::new(config);
+                let best = mcts.search(game);
+                let stats = mcts.root_stats();
+                // A proven win is the only move worth playing
+                if let Some(win) = stats.iter().find(|stats| stats.proof == Some(Proof::Win)) {
+                    return vec![BookMove {
+                        move_: win.move_,
+                        weight: win.visits.max(1),
+                    }];
+                }
+                let playable = || {
+                    stats
+                        .iter()
+                        .filter(|stats| stats.proof != Some(Proof::Loss) && stats.visits > 0)
+                };
+                let most = playable().map(|stats| stats.visits).max().unwrap_or(0);
+                let moves: Vec<_> = playable()
+                    .filter(|stats| stats.visits as f64 >= self.config.min_share * most as f64)
+                    .map(|stats| BookMove {
+                        move_: stats.move_,
+                        weight: stats.visits,
+                    })
+                    .collect();
+                if moves.is_empty() {
+                    // Every move loses
+                    vec![BookMove {
+                        move_: best,
+                        weight: 1,
+                    }]
+                } else {
+                    moves
+                }
+            }
+            BookSearch::AlphaBeta(limits) => vec![BookMove {
+                move_: AlphaBeta::new(limits).search(game).best_move,
+                weight: 1,
+            }],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::seeded;
+
+    #[test]
+    fn folds_symmetric_moves() {
+        let mut book = Book::new();
+        // All four corners of the empty grid are the same move
+        for move_ in [(0, 0, 0, 0), (0, 2, 0, 2), (2, 0, 2, 0), (2, 2, 2, 2)] {
+            book.insert(&Game::new(), move_, 1);
+        }
+        book.insert(&Game::new(), (1, 1, 1, 1), 2);
+        assert_eq!(book.len(), 1);
+        let moves = book.moves(&Game::new());
+        assert_eq!(moves.len(), 2);
+        assert!(moves.contains(&BookMove {
+            move_: (0, 0, 0, 0),
+            weight: 4
+        }));
+
+        // A reflected position finds the reflected move
+        let game = apply_move((0, 0, 1, 2), Game::new()).unwrap();
+        book.insert(&game, (1, 2, 0, 0), 1);
+        let mirrored = apply_move((0, 2, 1, 0), Game::new()).unwrap();
+        assert_eq!(
+            book.moves(&mirrored),
+            [BookMove {
+                move_: (1, 0, 0, 2),
+                weight: 1
+            }]
+        );
+        assert_eq!(book.choose(&mirrored, &mut seeded(0)), Some((1, 0, 0, 2)));
+        assert_eq!(
+            book.choose(
+                &apply_move((1, 1, 1, 1), Game::new()).unwrap(),
+                &mut seeded(0)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut builder = BookBuilder::new(BookConfig {
+            search: BookSearch::Mcts(MctsConfig {
+                limits: SearchLimits::iterations(300),
+                seed: Some(0),
+                ..Default::default()
+            }),
+            depth: 3,
+            positions: 6,
+            min_share: 0.5,
+        });
+        let searched = builder.grow(Game::new(), |game, moves| {
+            assert!(!moves.is_empty());
+            for book_move in moves {
+                assert!(game.legal_moves().contains(&book_move.move_));
+            }
+        });
+        assert_eq!(searched, 6);
+        let book = builder.into_book();
+        assert_eq!(book.len(), 6);
+
+        let mut bytes = Vec::new();
+        book.write(&mut bytes).unwrap();
+        assert_eq!(Book::read(&bytes[..]).unwrap(), book);
+        let truncated = Book::read(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(truncated.kind(), io::ErrorKind::UnexpectedEof);
+        let not_a_book = Book::read(&b"STTTPOS\x01"[..]).unwrap_err();
+        assert_eq!(not_a_book.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(Book::read(Interrupting(&bytes[..], false)).unwrap(), book);
+    }
+
+    /// A reader whose every other read is interrupted
+    struct Interrupting<'a>(&'a [u8], bool);
+
+    impl Read for Interrupting<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.1 = !self.1;
+            if self.1 {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            self.0.read(buf)
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! This module contains the errors that [`ai`](crate) may return.
+
+use std::{fmt, io};
+
+#[derive(Debug)]
+/// A weight file couldn't be read. Used by [`Weights`](crate::eval::Weights)
+pub enum WeightsError {
+    /// The file couldn't be read
+    Io(io::Error),
+    /// A line (numbered from 1) isn't a feature name followed by a finite number
+    InvalidLine(usize),
+    /// The file sets a weight for a feature that doesn't exist
+    UnknownFeature(String),
+}
+
+impl fmt::Display for WeightsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightsError::Io(err) => write!(f, "couldn't read the weights: {err}"),
+            WeightsError::InvalidLine(line) => {
+                write!(f, "line {line} isn't a feature name followed by a weight")
+            }
+            WeightsError::UnknownFeature(name) => write!(f, "there is no feature called {name:?}"),
+        }
+    }
+}
+impl std::error::Error for WeightsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WeightsError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for WeightsError {
+    fn from(err: io::Error) -> Self {
+        WeightsError::Io(err)
+    }
+}
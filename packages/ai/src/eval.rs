@@ -0,0 +1,281 @@
+//! Static evaluation: judging a position without searching any further.
+//!
+//! An [`Evaluator`] is what a search like [alpha-beta](crate::alphabeta) calls at the end of a line.
+//! [`HandcraftedEvaluator`] adds up a handful of features, each multiplied by its weight:
+//!
+//! | Feature          | Counts                                                                 |
+//! |------------------|------------------------------------------------------------------------|
+//! | `boards_won`     | Small boards won                                                       |
+//! | `centre_board`   | Whether the centre board is won                                        |
+//! | `corner_boards`  | Corner boards won                                                      |
+//! | `macro_threats`  | Lines of the big board with two boards won and the third still open    |
+//! | `small_threats`  | Squares that would win a small board that's still open                 |
+//! | `centre_squares` | Pieces in the centre of small boards that are still open               |
+//! | `corner_squares` | Pieces in the corners of small boards that are still open              |
+//! | `free_move`      | Whether the player to move may play anywhere because they were sent to a finished board |
+//!
+//! Every feature is counted for the player to move minus their opponent (except `free_move`,
+//! which only the player to move can have), so scores are always from the perspective of
+//! the player to move. Finished games aren't evaluated; the search scores them itself.
+//!
+//! [`Weights`] can be saved to and loaded from a text file with a feature name and
+//! its weight on every line. Blank lines and lines starting with `#` are ignored,
+//! and features the file doesn't mention keep their default weight:
+//!
+//! ```text
+//! # Weights for the handcrafted evaluator
+//! boards_won 100
+//! macro_threats 80
+//! ```
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use super_ttt::{Game, GameState, Player, Square};
+
+use crate::errors::WeightsError;
+
+/// The number of features [`HandcraftedEvaluator`] looks at
+pub const FEATURE_COUNT: usize = 8;
+/// The names of the features, in the order of [`features`] and [`Weights`]
+pub const FEATURE_NAMES: [&str; FEATURE_COUNT] = [
+    "boards_won",
+    "centre_board",
+    "corner_boards",
+    "macro_threats",
+    "small_threats",
+    "centre_squares",
+    "corner_squares",
+    "free_move",
+];
+const BOARDS_WON: usize = 0;
+const CENTRE_BOARD: usize = 1;
+const CORNER_BOARDS: usize = 2;
+const MACRO_THREATS: usize = 3;
+const SMALL_THREATS: usize = 4;
+const CENTRE_SQUARES: usize = 5;
+const CORNER_SQUARES: usize = 6;
+const FREE_MOVE: usize = 7;
+
+/// Every line of a 3x3 board
+const LINES: [[(usize, usize); 3]; 8] = [
+    [(0, 0), (0, 1), (0, 2)],
+    [(1, 0), (1, 1), (1, 2)],
+    [(2, 0), (2, 1), (2, 2)],
+    [(0, 0), (1, 0), (2, 0)],
+    [(0, 1), (1, 1), (2, 1)],
+    [(0, 2), (1, 2), (2, 2)],
+    [(0, 0), (1, 1), (2, 2)],
+    [(0, 2), (1, 1), (2, 0)],
+];
+
+/// Judges how good a position is without searching it
+pub trait Evaluator {
+    /// How good `game`, which is still in progress, is for the player to move.
+    /// Positive is good for them and `0` is even. Keep scores well below [`WIN`](crate::alphabeta::WIN)
+    fn evaluate(&self, game: &Game) -> i32;
+}
+
+impl<F: Fn(&Game) -> i32> Evaluator for F {
+    fn evaluate(&self, game: &Game) -> i32 {
+        self(game)
+    }
+}
+
+/// The weight of every feature, in the order of [`FEATURE_NAMES`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights(pub [f64; FEATURE_COUNT]);
+
+impl Default for Weights {
+    /// Educated guesses
+    fn default() -> Self {
+        Weights([100.0, 40.0, 15.0, 80.0, 12.0, 4.0, 2.0, 25.0])
+    }
+}
+
+impl Weights {
+    /// Read weights from a file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WeightsError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+    /// Write the weights to a file that [`Weights::load`] can read
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Weights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, weight) in FEATURE_NAMES.iter().zip(self.0) {
+            writeln!(f, "{name} {weight}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Weights {
+    type Err = WeightsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = Weights::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (Some(name), Some(weight), None) = (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(WeightsError::InvalidLine(i + 1));
+            };
+            let feature = FEATURE_NAMES
+                .iter()
+                .position(|&feature| feature == name)
+                .ok_or_else(|| WeightsError::UnknownFeature(name.to_string()))?;
+            weights.0[feature] = weight
+                .parse()
+                .ok()
+                .filter(|weight: &f64| weight.is_finite())
+                .ok_or(WeightsError::InvalidLine(i + 1))?;
+        }
+        Ok(weights)
+    }
+}
+
+/// Count every feature of `game`, from the perspective of the player to move.
+/// See the [module docs](self) for what they are
+pub fn features(game: &Game) -> [f64; FEATURE_COUNT] {
+    let me = game.current_player;
+    let sign = |player: Player| if player == me { 1.0 } else { -1.0 };
+    let mut features = [0.0; FEATURE_COUNT];
+    let mut states = [[GameState::InProgress; 3]; 3];
+    for (board_row, boards) in game.boards.iter().enumerate() {
+        for (board_col, board) in boards.iter().enumerate() {
+            let info = board.info();
+            states[board_row][board_col] = info.state;
+            match info.state {
+                GameState::Winner(player) => {
+                    features[BOARDS_WON] += sign(player);
+                    if (board_row, board_col) == (1, 1) {
+                        features[CENTRE_BOARD] += sign(player);
+                    } else if board_row != 1 && board_col != 1 {
+                        features[CORNER_BOARDS] += sign(player);
+                    }
+                }
+                GameState::InProgress => {
+                    features[SMALL_THREATS] += info.winning_squares(me).count_ones() as f64
+                        - info.winning_squares(me.opponent()).count_ones() as f64;
+                    for ((row, col), square) in board.iter_squares() {
+                        let Square::Occupied(player) = square else {
+                            continue;
+                        };
+                        if (row, col) == (1, 1) {
+                            features[CENTRE_SQUARES] += sign(player);
+                        } else if row != 1 && col != 1 {
+                            features[CORNER_SQUARES] += sign(player);
+                        }
+                    }
+                }
+                GameState::Tie => {}
+            }
+        }
+    }
+    for line in LINES {
+        let line = line.map(|(row, col)| states[row][col]);
+        for player in [Player::X, Player::O] {
+            let won = line
+                .iter()
+                .filter(|&&state| state == GameState::Winner(player))
+                .count();
+            if won == 2 && line.contains(&GameState::InProgress) {
+                features[MACRO_THREATS] += sign(player);
+            }
+        }
+    }
+    if let Some((row, col)) = game.last_move_cords {
+        if states[row][col] != GameState::InProgress {
+            features[FREE_MOVE] = 1.0;
+        }
+    }
+    features
+}
+
+/// A weighted sum of a few features that matter in super tic tac toe.
+/// See the [module docs](self) for what they are
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HandcraftedEvaluator {
+    /// How much each feature is worth
+    pub weights: Weights,
+}
+
+impl HandcraftedEvaluator {
+    /// An evaluator that uses `weights`
+    pub fn new(weights: Weights) -> Self {
+        HandcraftedEvaluator { weights }
+    }
+}
+
+impl Evaluator for HandcraftedEvaluator {
+    fn evaluate(&self, game: &Game) -> i32 {
+        features(game)
+            .iter()
+            .zip(self.weights.0)
+            .map(|(feature, weight)| feature * weight)
+            .sum::<f64>()
+            .round() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_features() {
+        // X has won the top left and centre boards, so it only needs the bottom right one,
+        // where O is sent and can win with a single move
+        let game = Game::from_position_string(
+            "XXX....../.O......./........./...XXX.../........./........./......OO./O......../...O..... O 8",
+        )
+        .unwrap();
+        assert_eq!(
+            features(&game),
+            [-2.0, -1.0, -1.0, -1.0, 1.0, 0.0, 2.0, 0.0]
+        );
+        let evaluator = HandcraftedEvaluator::default();
+        assert!(evaluator.evaluate(&game) < 0);
+
+        // Anything that works like an evaluator is one
+        let zero = |_: &Game| 0;
+        assert_eq!(zero.evaluate(&game), 0);
+    }
+
+    #[test]
+    fn weights_round_trip() {
+        let weights = Weights([1.0, -2.5, 3.0, 4.0, 5.0, 6.0, 7.0, 0.125]);
+        assert_eq!(weights.to_string().parse::<Weights>().unwrap(), weights);
+
+        let partial: Weights = "# only one\n\nboards_won 7\n".parse().unwrap();
+        assert_eq!(partial.0[0], 7.0);
+        assert_eq!(partial.0[1..], Weights::default().0[1..]);
+
+        assert!(matches!(
+            "boards_won".parse::<Weights>(),
+            Err(WeightsError::InvalidLine(1))
+        ));
+        assert!(matches!(
+            "\nboards_won seven".parse::<Weights>(),
+            Err(WeightsError::InvalidLine(2))
+        ));
+        for weight in ["NaN", "inf", "-infinity"] {
+            assert!(matches!(
+                format!("boards_won {weight}").parse::<Weights>(),
+                Err(WeightsError::InvalidLine(1))
+            ));
+        }
+        assert!(matches!(
+            "tempo 3".parse::<Weights>(),
+            Err(WeightsError::UnknownFeature(name)) if name == "tempo"
+        ));
+    }
+}
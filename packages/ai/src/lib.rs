@@ -1,46 +1,66 @@
-use rand::{self, seq::SliceRandom};
+use rand::{seq::SliceRandom, Rng};
 
-use super_ttt::{errors, Game, GameState, Player, Square};
+pub mod agent;
+pub mod alphabeta;
+pub mod book;
+pub mod errors;
+pub mod eval;
+pub mod limits;
+pub mod mcts;
+pub mod nn;
+pub mod playout;
+pub mod pns;
+pub mod policy;
+pub mod puct;
+pub mod rng;
+pub mod selfplay;
+pub mod symmetry;
+#[cfg(test)]
+mod test_positions;
+pub mod tuning;
+mod zobrist;
+
+use super_ttt::errors::InvalidMoveError;
+use super_ttt::{from_square_coords, to_square_coords, Game, GameState, Player, GRID_SIZE};
 pub fn apply_move(
     (board_row, board_col, cell_row, cell_col): (usize, usize, usize, usize),
     mut game: Game,
-) -> Result<Game, errors::InvalidMoveError> {
+) -> Result<Game, InvalidMoveError> {
     game.make_move(board_row, board_col, cell_row, cell_col)
 }
-pub type Move = (usize, usize, usize, usize);
-pub fn get_valid_moves(node: Game) -> Vec<Move> {
-    let mut valid_moves = Vec::new();
-    let mut row_range = 0..3;
-    let mut col_range = 0..3;
-
-    if let Some(last_move_cord) = node.last_move_cords {
-        if node.boards[last_move_cord.0][last_move_cord.1].get_winner() == GameState::InProgress {
-            row_range = last_move_cord.0..last_move_cord.0 + 1;
-            col_range = last_move_cord.1..last_move_cord.1 + 1;
-        }
-    }
-
-    for i in row_range {
-        for j in col_range.clone() {
-            for k in 0..3 {
-                for l in 0..3 {
-                    if node.boards[i][j].squares[k][l] == Square::Empty {
-                        valid_moves.push((i, j, k, l));
-                    }
-                }
-            }
-        }
+pub use super_ttt::{Move, MoveList};
+/// The index of `move_` on the whole grid, which is its square coordinate `row * 9 + col`.
+/// Tables and masks with an entry for every square are indexed by it
+pub fn square_index((board_row, board_col, cell_row, cell_col): Move) -> usize {
+    let (row, col) = to_square_coords(board_row, board_col, cell_row, cell_col);
+    row * GRID_SIZE + col
+}
+/// The move on the square with [`square_index`] `index`
+pub fn index_move(index: usize) -> Move {
+    from_square_coords(index / GRID_SIZE, index % GRID_SIZE)
+}
+/// `0` for `X` and `1` for `O`, for tables with an entry for each player
+pub fn player_index(player: Player) -> usize {
+    match player {
+        Player::X => 0,
+        Player::O => 1,
     }
-
-    valid_moves
 }
-pub fn simulate_game(node: Game) -> (Vec<Game>, i8) {
+/// Every valid move in `node`. Prefer [`Game::legal_moves`], which doesn't allocate
+pub fn get_valid_moves(node: Game) -> Vec<Move> {
+    node.legal_moves().to_vec()
+}
+/// Play random moves, drawn from `rng`, from `node` until the game ends. Returns every
+/// position along the way and the result from `X`'s perspective (`1`, `0` or `-1`).
+/// Prefer [`playout::playout`], which doesn't allocate
+pub fn simulate_game(node: Game, rng: &mut impl Rng) -> (Vec<Game>, i8) {
     let mut current_node = node;
     let mut visited_nodes = vec![node];
     while current_node.get_winner() == GameState::InProgress {
         current_node = apply_move(
-            *get_valid_moves(current_node)
-                .choose(&mut rand::thread_rng())
+            *current_node
+                .legal_moves()
+                .choose(rng)
                 .expect("No valid moves"),
             current_node,
         )
@@ -52,13 +72,7 @@ pub fn simulate_game(node: Game) -> (Vec<Game>, i8) {
         visited_nodes,
         match current_node.get_winner() {
             GameState::Tie => 0,
-            GameState::Winner(player) => {
-                if player == Player::X {
-                    1
-                } else {
-                    -1
-                }
-            }
+            GameState::Winner(player) => player.to_sign(),
             GameState::InProgress => unreachable!(),
         },
     )
@@ -0,0 +1,109 @@
+//! How long a search is allowed to run.
+//!
+//! Every limit is optional and a search stops as soon as it reaches any of them,
+//! playing the best move it has found so far.
+use std::time::{Duration, Instant};
+
+/// Limits on a single search. Leave a field as `None` to not limit it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// Wall-clock time, measured from the start of the search
+    pub time: Option<Duration>,
+    /// Iterations of the search, like MCTS playouts
+    pub iterations: Option<usize>,
+    /// Nodes in the search tree, including ones kept from earlier searches
+    pub nodes: Option<usize>,
+    /// An (approximate) ceiling for the memory the search tree may use, in bytes
+    pub memory: Option<usize>,
+}
+
+impl SearchLimits {
+    /// Only limit the number of iterations
+    pub fn iterations(iterations: usize) -> Self {
+        SearchLimits {
+            iterations: Some(iterations),
+            ..Default::default()
+        }
+    }
+    /// Only limit the time, as for a move in a timed game
+    pub fn time(time: Duration) -> Self {
+        SearchLimits {
+            time: Some(time),
+            ..Default::default()
+        }
+    }
+    /// Whether none of the limits are set, so a search would never stop
+    pub fn is_unlimited(&self) -> bool {
+        *self == SearchLimits::default()
+    }
+    /// The most nodes a tree may have when each one takes `node_bytes` bytes
+    pub(crate) fn max_nodes(&self, node_bytes: usize) -> usize {
+        let by_memory = self.memory.map_or(usize::MAX, |memory| memory / node_bytes);
+        self.nodes.unwrap_or(usize::MAX).min(by_memory)
+    }
+    /// Thread number `thread`'s share of the limits when `threads` threads search on their own.
+    /// The iteration, node and memory budgets are split evenly, and every thread gets the full time
+    pub(crate) fn share(&self, thread: usize, threads: usize) -> SearchLimits {
+        let split = |total: usize| total / threads + usize::from(thread < total % threads);
+        SearchLimits {
+            time: self.time,
+            iterations: self.iterations.map(split),
+            nodes: self.nodes.map(split),
+            memory: self.memory.map(split),
+        }
+    }
+    /// Whether a search that began at `start`, ran `iterations` iterations and
+    /// grew its tree to `nodes` nodes of `node_bytes` bytes each has to stop
+    pub(crate) fn reached(
+        &self,
+        start: Instant,
+        iterations: usize,
+        nodes: usize,
+        node_bytes: usize,
+    ) -> bool {
+        self.iterations.is_some_and(|limit| iterations >= limit)
+            || nodes >= self.max_nodes(node_bytes)
+            || self.time.is_some_and(|limit| start.elapsed() >= limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_limit_wins() {
+        let start = Instant::now();
+        let limits = SearchLimits {
+            iterations: Some(100),
+            nodes: Some(50),
+            memory: Some(1_000),
+            ..Default::default()
+        };
+        assert!(!limits.reached(start, 10, 10, 10));
+        assert!(limits.reached(start, 100, 10, 10));
+        assert!(limits.reached(start, 10, 50, 10));
+        // 1000 bytes only fit 10 nodes of 100 bytes
+        assert!(limits.reached(start, 10, 10, 100));
+
+        let limits = SearchLimits::time(Duration::ZERO);
+        assert!(limits.reached(start, 0, 0, 1));
+        assert!(SearchLimits::default().is_unlimited());
+        assert!(!SearchLimits::iterations(1).is_unlimited());
+    }
+
+    #[test]
+    fn shares_add_up() {
+        let limits = SearchLimits {
+            iterations: Some(10),
+            time: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let shares: Vec<SearchLimits> = (0..4).map(|thread| limits.share(thread, 4)).collect();
+        let iterations: Vec<usize> = shares.iter().map(|s| s.iterations.unwrap()).collect();
+        assert_eq!(iterations, [3, 3, 2, 2]);
+        assert!(shares
+            .iter()
+            .all(|s| s.time == limits.time && s.nodes.is_none()));
+    }
+}
@@ -0,0 +1,1084 @@
+//! [Monte Carlo Tree Search](https://en.wikipedia.org/wiki/Monte_Carlo_tree_search)
+//!
+//! Each iteration of the search has four phases:
+//!
+//! 1. **Selection:** starting at the root, descend through fully expanded nodes,
+//!    picking the child with the highest [UCT](https://en.wikipedia.org/wiki/Monte_Carlo_tree_search#Exploration_and_exploitation) value
+//! 2. **Expansion:** add one untried move of the selected node to the tree
+//! 3. **Simulation:** play moves from the new node until the game ends, picked by a
+//!    [rollout policy](crate::policy) (uniformly random unless [`Mcts::with_policy`] says otherwise)
+//! 4. **Backpropagation:** add the result to every node on the way back up to the root
+//!
+//! Results are scored `1` for a win, `0.5` for a tie and `0` for a loss, always from the
+//! perspective of the player who made the move leading into a node. That way every
+//! node's parent simply maximizes over its children's values (negamax).
+//!
+//! The tree is kept between searches. After a move is played (by either side), [`Mcts::advance`]
+//! makes that move's node the new root, so everything learned about the rest of the game
+//! is kept while the discarded branches are freed. [`Mcts::search`] does this on its own when
+//! it's given a position up to two moves deeper than the current root.
+//!
+//! With [RAVE](MctsConfig::rave) (rapid action value estimation), every node also keeps
+//! *all moves as first* (AMAF) statistics for each of its moves: the results of every playout
+//! through the node in which the player to move played that square at any point later on, not
+//! just right away. They're biased, but there are a lot more of them, so early on a move's value
+//! is mostly its AMAF value, and the real value takes over as its visits pile up (see [`RaveSchedule`]).
+//!
+//! The search is also an MCTS-Solver: finished games are proven wins or draws for the player
+//! who just moved, and proofs propagate up the tree. A node is a proven loss for the player who
+//! moved into it as soon as one reply wins, and proven once all of its moves are proven
+//! (a win if they all lose, a draw otherwise). Selection never walks into a proven loss again
+//! and stops at other proven nodes without a playout, the search ends as soon as the root is
+//! proven, and a proven win is always the move played.
+//!
+//! A search runs until it reaches one of its [`SearchLimits`]. The `nodes` and `memory`
+//! limits cap the whole tree, including the part kept from earlier searches.
+//!
+//! With more than one [thread](MctsConfig::threads), the search runs on a [rayon] thread pool
+//! in one of two ways (see [`Parallelism`]):
+//!
+//! - **Root parallelisation:** every thread grows its own tree from the root, and the trees
+//!   are merged into one when time is up. The threads never wait for each other, but they
+//!   duplicate a lot of work near the root
+//! - **Tree parallelisation:** all threads share one tree behind a lock, which they only hold
+//!   while selecting, expanding and backpropagating. Every node on the way to a leaf gets a
+//!   *virtual loss* (a visit without a win) until its playout finishes, so the other threads
+//!   are steered towards different leaves in the meantime
+//!
+//! Playouts draw their moves from a [generator](crate::rng) seeded by [`MctsConfig::seed`], and every
+//! thread of a parallel search gets its own [task stream](crate::rng::task_rng). A search limited by
+//! iterations, nodes or memory plays out exactly the same way every time for the same seed, on one
+//! thread or with root parallelisation. With tree parallelisation, the timing of the threads
+//! decides which of them plays out which leaf, so those searches can't be reproduced.
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use rand::Rng;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use super_ttt::{Game, GameState, Move, Player};
+
+use crate::limits::SearchLimits;
+use crate::playout::rollout_with;
+use crate::policy::{RolloutPolicy, Uniform};
+use crate::rng::{self, GameRng};
+use crate::{apply_move, index_move, player_index, square_index};
+
+/// How an [`Mcts`] searches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsConfig {
+    /// When to stop searching. Iterations are playouts. At least one playout is always run,
+    /// even if the tree is already over its `nodes` or `memory` limit
+    pub limits: SearchLimits,
+    /// The exploration constant `c` of UCT. Higher values explore more, lower values
+    /// focus on the moves that look best so far. Defaults to `√2`
+    pub exploration: f64,
+    /// Whether to keep the subtree of the position being searched from earlier searches.
+    /// When `false`, every search starts from scratch
+    pub reuse_tree: bool,
+    /// How many threads to search with. `0` uses one per core
+    pub threads: usize,
+    /// How the threads split up the work, if there's more than one
+    pub parallelism: Parallelism,
+    /// The seed for the random playouts. `None` seeds from the operating system
+    pub seed: Option<u64>,
+    /// Blend in AMAF values with this schedule, or `None` for plain UCT. See the [module docs](self)
+    pub rave: Option<RaveSchedule>,
+    /// Whether to propagate proven wins and losses up the tree. See the [module docs](self)
+    pub solver: bool,
+}
+
+/// How much weight `β` [RAVE](MctsConfig::rave) gives a move's AMAF value, which is blended
+/// with its real value as `(1 - β) * value + β * amaf_value`. `n` is the number of real visits
+/// and `ñ` the number of AMAF visits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RaveSchedule {
+    /// `β = √(k / (3n + k))`, so the two values weigh the same after `k` visits
+    Equivalence(f64),
+    /// `β = ñ / (n + ñ + 4b²nñ)`, which minimizes the mean squared error of the blend
+    /// if the AMAF values are off by `b` on average
+    MinimumMse(f64),
+}
+
+impl Default for RaveSchedule {
+    fn default() -> Self {
+        RaveSchedule::Equivalence(1_000.0)
+    }
+}
+
+impl RaveSchedule {
+    /// The weight of the AMAF value of a move with `visits` real visits and `amaf_visits` AMAF visits
+    pub fn beta(self, visits: u32, amaf_visits: u32) -> f64 {
+        let (n, amaf_n) = (visits as f64, amaf_visits as f64);
+        match self {
+            RaveSchedule::Equivalence(k) => (k / (3.0 * n + k)).sqrt(),
+            RaveSchedule::MinimumMse(bias) => {
+                amaf_n / (n + amaf_n + 4.0 * bias * bias * n * amaf_n)
+            }
+        }
+    }
+}
+
+/// How a multi-threaded [`Mcts`] searches. See the [module docs](self)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Parallelism {
+    /// Every thread searches its own tree, and the trees are merged at the end.
+    /// The iteration, node and memory limits are split evenly between the threads
+    Root,
+    /// All threads search one shared tree, spreading out by virtual loss
+    #[default]
+    Tree,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            limits: SearchLimits::iterations(1_000),
+            exploration: std::f64::consts::SQRT_2,
+            reuse_tree: true,
+            threads: 1,
+            parallelism: Parallelism::Tree,
+            seed: None,
+            rave: None,
+            solver: true,
+        }
+    }
+}
+
+/// What the search found out about a move from the root
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveStats {
+    /// The move
+    pub move_: Move,
+    /// How many playouts went through this move
+    pub visits: u32,
+    /// The average result for the player making the move: `1` is a certain win, `0` a certain loss
+    pub value: f64,
+    /// What the move is known to lead to, if the search proved it
+    pub proof: Option<Proof>,
+}
+
+/// A result the [solver](self) proved, for the player making a move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Proof {
+    /// The move wins, whatever the opponent does
+    Win,
+    /// The best the player can get after this move is a tie
+    Draw,
+    /// The move loses, whatever the player does afterwards
+    Loss,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    game: Game,
+    parent: Option<usize>,
+    /// The move that led from the parent to this node
+    move_: Option<Move>,
+    children: Vec<usize>,
+    /// Moves that don't have a child yet, as bit [`square_index`] of each.
+    /// A mask rather than a list keeps nodes small
+    untried: u128,
+    visits: u32,
+    /// Sum of the playout results, from the perspective of the player who moved into this node
+    score: f64,
+    /// The AMAF statistics of the move into this node, like `visits` and `score`
+    amaf_visits: u32,
+    amaf_score: f64,
+    /// What the move into this node is proven to lead to
+    proof: Option<Proof>,
+}
+
+/// Roughly how much memory a node takes: itself plus its index in its parent's `children`
+const NODE_BYTES: usize = std::mem::size_of::<Node>() + 2 * std::mem::size_of::<usize>();
+
+impl Node {
+    fn new(game: Game, parent: Option<usize>, move_: Option<Move>) -> Self {
+        let state = game.get_winner();
+        let untried = if state == GameState::InProgress {
+            game.legal_moves()
+                .iter()
+                .fold(0, |mask, &move_| mask | 1 << square_index(move_))
+        } else {
+            0
+        };
+        // Only the player who just moved can have won
+        let proof = match state {
+            GameState::Winner(_) => Some(Proof::Win),
+            GameState::Tie => Some(Proof::Draw),
+            GameState::InProgress => None,
+        };
+        Node {
+            game,
+            parent,
+            move_,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            score: 0.0,
+            amaf_visits: 0,
+            amaf_score: 0.0,
+            proof,
+        }
+    }
+    fn value(&self) -> f64 {
+        self.score / self.visits as f64
+    }
+}
+
+/// The squares each player played, `X` first, with bit `square_index` for every move
+type Played = [u128; 2];
+
+/// Play `game` out with `policy`, noting which squares each player took along the way
+fn simulate(game: Game, policy: &impl RolloutPolicy, rng: &mut GameRng) -> (GameState, Played) {
+    let mut played = [0; 2];
+    let playout = rollout_with(game, policy, rng, |game, move_| {
+        played[player_index(game.current_player)] |= 1 << square_index(move_);
+    });
+    (playout.result, played)
+}
+
+/// Score a finished game for `player`: `1` for a win, `0.5` for a tie and `0` for a loss
+fn reward(state: GameState, player: Player) -> f64 {
+    match state {
+        GameState::Winner(winner) if winner == player => 1.0,
+        GameState::Winner(_) => 0.0,
+        GameState::Tie => 0.5,
+        GameState::InProgress => unreachable!("only finished games are scored"),
+    }
+}
+
+/// A Monte Carlo Tree Search engine whose playouts are picked by `P`
+#[derive(Debug, Clone)]
+pub struct Mcts<P = Uniform> {
+    config: MctsConfig,
+    policy: P,
+    /// Every node of the tree. The root is always at index 0
+    nodes: Vec<Node>,
+    /// Started by the first multi-threaded search
+    pool: Option<Arc<ThreadPool>>,
+    rng: GameRng,
+}
+
+impl Mcts {
+    /// Create an engine with uniformly random playouts that searches according to `config`
+    pub fn new(config: MctsConfig) -> Self {
+        Self::with_policy(config, Uniform)
+    }
+}
+
+impl<P: RolloutPolicy + Clone + Send + Sync> Mcts<P> {
+    /// Create an engine that searches according to `config` and picks the moves of its playouts with `policy`
+    pub fn with_policy(config: MctsConfig, policy: P) -> Self {
+        Mcts {
+            config,
+            policy,
+            nodes: Vec::new(),
+            pool: None,
+            rng: config.seed.map_or_else(rng::from_entropy, rng::seeded),
+        }
+    }
+    /// The configuration this engine searches with
+    pub fn config(&self) -> &MctsConfig {
+        &self.config
+    }
+    /// The policy that picks the moves of the playouts
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+    /// Search `root` until one of the [limits](MctsConfig::limits) is reached and return
+    /// the best move for the player to move, which is the most visited one.
+    ///
+    /// If `root` is already in the tree within two moves of the current root, the search
+    /// continues from the statistics collected so far (unless [`MctsConfig::reuse_tree`] is off).
+    ///
+    /// # Panics
+    /// Panics if the game is already over or the search has no limits at all
+    pub fn search(&mut self, root: Game) -> Move {
+        let start = Instant::now();
+        assert_eq!(
+            root.get_winner(),
+            GameState::InProgress,
+            "can't search a finished game"
+        );
+        assert!(
+            !self.config.limits.is_unlimited(),
+            "the search would never stop"
+        );
+        if !self.config.reuse_tree || !self.move_root_to(root) {
+            self.reset(root);
+        }
+        let threads = match self.config.threads {
+            0 => std::thread::available_parallelism().map_or(1, |cores| cores.get()),
+            threads => threads,
+        };
+        // Every root-parallel tree is searched at least once, so each needs an iteration of its own
+        let threads = match (self.config.parallelism, self.config.limits.iterations) {
+            (Parallelism::Root, Some(iterations)) => threads.min(iterations).max(1),
+            _ => threads,
+        };
+        match (threads, self.config.parallelism) {
+            (1, _) => self.search_serial(start, self.config.limits),
+            (_, Parallelism::Root) => self.search_root_parallel(start, threads),
+            (_, Parallelism::Tree) => self.search_tree_parallel(start, threads),
+        }
+        self.best_move()
+            .expect("the root has children after a search")
+    }
+    /// Statistics for every move from the root that has been tried, after a [`Mcts::search`]
+    pub fn root_stats(&self) -> Vec<MoveStats> {
+        self.nodes.first().map_or_else(Vec::new, |root| {
+            root.children
+                .iter()
+                .map(|&child| {
+                    let child = &self.nodes[child];
+                    MoveStats {
+                        move_: child.move_.unwrap(),
+                        visits: child.visits,
+                        value: child.value(),
+                        proof: child.proof,
+                    }
+                })
+                .collect()
+        })
+    }
+    /// A proven win if there is one, otherwise the most visited move from the root that isn't
+    /// a proven loss (unless they all are). `None` if nothing has been searched
+    pub fn best_move(&self) -> Option<Move> {
+        self.root_stats()
+            .into_iter()
+            .max_by_key(|stats| {
+                let rank = match stats.proof {
+                    Some(Proof::Win) => 2,
+                    Some(Proof::Loss) => 0,
+                    _ => 1,
+                };
+                (rank, stats.visits)
+            })
+            .map(|stats| stats.move_)
+    }
+    /// What the move that led to the root is proven to lead to, from the perspective of the
+    /// player who made it. [`Proof::Loss`] means the player to move at the root has a forced win
+    pub fn root_proof(&self) -> Option<Proof> {
+        self.nodes.first().and_then(|root| root.proof)
+    }
+    /// The number of nodes in the search tree
+    pub fn tree_size(&self) -> usize {
+        self.nodes.len()
+    }
+    /// An estimate of the memory the search tree takes, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.nodes.capacity() * NODE_BYTES
+    }
+    /// The position at the root of the tree, if there is one
+    pub fn root(&self) -> Option<Game> {
+        self.nodes.first().map(|root| root.game)
+    }
+    /// The number of playouts that went through the root
+    pub fn root_visits(&self) -> u32 {
+        self.nodes.first().map_or(0, |root| root.visits)
+    }
+    /// Throw away the whole tree
+    pub fn clear(&mut self) {
+        self.nodes = Vec::new();
+    }
+    /// Play `move_` from the root: its node becomes the new root and everything else
+    /// is freed. If the move was never expanded, the tree starts over from the resulting position
+    ///
+    /// # Panics
+    /// Panics if the tree is empty or `move_` isn't legal at the root
+    pub fn advance(&mut self, move_: Move) {
+        let root = self.nodes.first().expect("nothing has been searched yet");
+        match root
+            .children
+            .iter()
+            .find(|&&child| self.nodes[child].move_ == Some(move_))
+        {
+            Some(&child) => self.reroot(child),
+            None => {
+                let game = apply_move(move_, root.game).expect("move isn't legal at the root");
+                self.reset(game);
+            }
+        }
+    }
+
+    fn search_serial(&mut self, start: Instant, limits: SearchLimits) {
+        let max_nodes = limits.max_nodes(NODE_BYTES);
+        let mut iterations = 0;
+        while iterations == 0
+            || !(self.nodes[0].proof.is_some()
+                || limits.reached(start, iterations, self.nodes.len(), NODE_BYTES))
+        {
+            self.make_room(max_nodes);
+            let leaf = self.select();
+            let leaf = self.expand(leaf, max_nodes);
+            let (result, played) = match self.proven_result(leaf) {
+                Some(result) => (result, [0; 2]),
+                None => simulate(self.nodes[leaf].game, &self.policy, &mut self.rng),
+            };
+            self.backpropagate(leaf, result, played, false);
+            iterations += 1;
+        }
+    }
+    fn search_root_parallel(&mut self, start: Instant, threads: usize) {
+        let root = self.nodes[0].game;
+        let limits = self.config.limits;
+        let helper_config = MctsConfig {
+            threads: 1,
+            ..self.config
+        };
+        let seed = self.rng.gen();
+        let policy = self.policy.clone();
+        // This tree is one of the threads' trees, so it keeps what it had from earlier searches
+        let (_, helpers) = self.pool(threads).install(|| {
+            rayon::join(
+                || self.search_serial(start, limits.share(0, threads)),
+                || {
+                    (1..threads)
+                        .into_par_iter()
+                        .map(|thread| {
+                            let mut helper = Mcts::with_policy(helper_config, policy.clone());
+                            helper.rng = rng::task_rng(seed, thread as u64);
+                            helper.reset(root);
+                            helper.search_serial(start, limits.share(thread, threads));
+                            helper
+                        })
+                        .collect::<Vec<_>>()
+                },
+            )
+        });
+        let max_nodes = limits.max_nodes(NODE_BYTES);
+        for helper in &helpers {
+            self.merge(helper, 0, 0, max_nodes);
+        }
+    }
+    fn search_tree_parallel(&mut self, start: Instant, threads: usize) {
+        let limits = self.config.limits;
+        let max_nodes = limits.max_nodes(NODE_BYTES);
+        let pool = self.pool(threads);
+        let seed = self.rng.gen();
+        let policy = self.policy.clone();
+        // The tree and the number of playouts started so far
+        let shared = Mutex::new((&mut *self, 0));
+        pool.broadcast(|context| {
+            let mut rng = rng::task_rng(seed, context.index() as u64);
+            loop {
+                let (leaf, game, proven) = {
+                    let mut guard = shared.lock().unwrap();
+                    let (tree, iterations) = &mut *guard;
+                    if *iterations > 0
+                        && (tree.nodes[0].proof.is_some()
+                            || limits.reached(start, *iterations, tree.nodes.len(), NODE_BYTES))
+                    {
+                        break;
+                    }
+                    *iterations += 1;
+                    tree.make_room(max_nodes);
+                    let leaf = tree.select();
+                    let leaf = tree.expand(leaf, max_nodes);
+                    tree.add_virtual_loss(leaf);
+                    (leaf, tree.nodes[leaf].game, tree.proven_result(leaf))
+                };
+                let (result, played) = match proven {
+                    Some(result) => (result, [0; 2]),
+                    None => simulate(game, &policy, &mut rng),
+                };
+                shared
+                    .lock()
+                    .unwrap()
+                    .0
+                    .backpropagate(leaf, result, played, true);
+            }
+        });
+    }
+    /// The thread pool for searching with `threads` threads
+    fn pool(&mut self, threads: usize) -> Arc<ThreadPool> {
+        if !matches!(&self.pool, Some(pool) if pool.current_num_threads() == threads) {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .expect("couldn't start the search threads");
+            self.pool = Some(Arc::new(pool));
+        }
+        Arc::clone(self.pool.as_ref().unwrap())
+    }
+    /// Grow the arena by hand near the node limit, so doubling it can't blow through the ceiling
+    fn make_room(&mut self, max_nodes: usize) {
+        // A full tree doesn't grow at all, see `expand`
+        if self.nodes.len() == self.nodes.capacity() && self.nodes.len() < max_nodes {
+            let room = max_nodes - self.nodes.len();
+            self.nodes.reserve_exact(self.nodes.len().clamp(1, room));
+        }
+    }
+    /// Add the statistics of `other`'s subtree under `from` to the subtree under `into`,
+    /// which holds the same position. Moves only `other` tried get new nodes while the tree
+    /// has fewer than `max_nodes`, after that only the statistics of `into` itself count them
+    fn merge(&mut self, other: &Mcts<P>, into: usize, from: usize, max_nodes: usize) {
+        self.nodes[into].visits += other.nodes[from].visits;
+        self.nodes[into].score += other.nodes[from].score;
+        self.nodes[into].amaf_visits += other.nodes[from].amaf_visits;
+        self.nodes[into].amaf_score += other.nodes[from].amaf_score;
+        // A proof is a proof, whichever tree found it
+        self.nodes[into].proof = self.nodes[into].proof.or(other.nodes[from].proof);
+        for &other_child in &other.nodes[from].children {
+            let move_ = other.nodes[other_child].move_.unwrap();
+            let existing = self.nodes[into]
+                .children
+                .iter()
+                .copied()
+                .find(|&child| self.nodes[child].move_ == Some(move_));
+            let child = match existing {
+                Some(child) => child,
+                None if self.nodes.len() >= max_nodes => continue,
+                None => {
+                    self.make_room(max_nodes);
+                    self.nodes[into].untried &= !(1 << square_index(move_));
+                    let game = other.nodes[other_child].game;
+                    self.nodes.push(Node::new(game, Some(into), Some(move_)));
+                    let child = self.nodes.len() - 1;
+                    self.nodes[into].children.push(child);
+                    child
+                }
+            };
+            self.merge(other, child, other_child, max_nodes);
+        }
+        if self.config.solver {
+            self.prove(into);
+        }
+    }
+
+    fn reset(&mut self, root: Game) {
+        // A new arena rather than `clear`, so a big old tree doesn't count against the memory limit
+        self.nodes = vec![Node::new(root, None, None)];
+    }
+    /// Make the node holding `game` the root, if it's the root, a child or a grandchild
+    fn move_root_to(&mut self, game: Game) -> bool {
+        let Some(root) = self.nodes.first() else {
+            return false;
+        };
+        if root.game == game {
+            return true;
+        }
+        let found = root
+            .children
+            .iter()
+            .flat_map(|&child| std::iter::once(child).chain(self.nodes[child].children.clone()))
+            .find(|&node| self.nodes[node].game == game);
+        if let Some(node) = found {
+            self.reroot(node);
+        }
+        found.is_some()
+    }
+    /// Keep only the subtree under `new_root`, compacting it to the start of the arena
+    fn reroot(&mut self, new_root: usize) {
+        // Old index to new index, breadth first so the new root ends up at 0
+        let mut new_index = vec![usize::MAX; self.nodes.len()];
+        let mut order = vec![new_root];
+        new_index[new_root] = 0;
+        let mut i = 0;
+        while let Some(&old) = order.get(i) {
+            for &child in &self.nodes[old].children {
+                new_index[child] = order.len();
+                order.push(child);
+            }
+            i += 1;
+        }
+        // Options, so the nodes that are kept can be moved out one at a time
+        let mut old_nodes: Vec<Option<Node>> = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let mut nodes = Vec::with_capacity(order.len());
+        for old in order {
+            let mut node = old_nodes[old].take().unwrap();
+            node.parent = node
+                .parent
+                .map(|parent| new_index[parent])
+                .filter(|&p| p != usize::MAX);
+            node.children
+                .iter_mut()
+                .for_each(|child| *child = new_index[*child]);
+            nodes.push(node);
+        }
+        nodes[0].parent = None;
+        nodes[0].move_ = None;
+        self.nodes = nodes;
+    }
+
+    /// Descend from the root through fully expanded nodes by UCT, stopping at proven ones
+    fn select(&self) -> usize {
+        let mut current = 0;
+        while self.nodes[current].proof.is_none()
+            && self.nodes[current].untried == 0
+            && !self.nodes[current].children.is_empty()
+        {
+            let log_visits = (self.nodes[current].visits as f64).ln();
+            // An unproven node that's fully expanded has a move that isn't a proven loss
+            current = *self.nodes[current]
+                .children
+                .iter()
+                .filter(|&&child| self.nodes[child].proof != Some(Proof::Loss))
+                .max_by(|&&a, &&b| self.uct(a, log_visits).total_cmp(&self.uct(b, log_visits)))
+                .unwrap();
+        }
+        current
+    }
+    /// How the game ends from a proven node, which is what a playout from there would return
+    fn proven_result(&self, node: usize) -> Option<GameState> {
+        let node = &self.nodes[node];
+        let mover = node.game.current_player.opponent();
+        node.proof.map(|proof| match proof {
+            Proof::Win => GameState::Winner(mover),
+            Proof::Draw => GameState::Tie,
+            Proof::Loss => GameState::Winner(node.game.current_player),
+        })
+    }
+    /// Try to prove `node` from its children's proofs, and return whether it's proven
+    fn prove(&mut self, node: usize) -> bool {
+        if self.nodes[node].proof.is_some() {
+            return true;
+        }
+        // The children's proofs are for the player to move here
+        let mut all_proven = self.nodes[node].untried == 0;
+        let mut draw = false;
+        for &child in &self.nodes[node].children {
+            match self.nodes[child].proof {
+                Some(Proof::Win) => {
+                    self.nodes[node].proof = Some(Proof::Loss);
+                    return true;
+                }
+                Some(Proof::Draw) => draw = true,
+                Some(Proof::Loss) => {}
+                None => all_proven = false,
+            }
+        }
+        if all_proven {
+            self.nodes[node].proof = Some(if draw { Proof::Draw } else { Proof::Win });
+        }
+        all_proven
+    }
+    fn uct(&self, node: usize, log_parent_visits: f64) -> f64 {
+        let node = &self.nodes[node];
+        let value = match self.config.rave {
+            Some(schedule) if node.amaf_visits > 0 => {
+                let beta = schedule.beta(node.visits, node.amaf_visits);
+                (1.0 - beta) * node.value() + beta * node.amaf_score / node.amaf_visits as f64
+            }
+            _ => node.value(),
+        };
+        value + self.config.exploration * (log_parent_visits / node.visits as f64).sqrt()
+    }
+    /// Add a child for one of `node`'s untried moves and return it.
+    /// Proven nodes (like finished games) aren't expanded, and neither is anything once the
+    /// tree has `max_nodes` nodes, so they are returned as is
+    fn expand(&mut self, node: usize, max_nodes: usize) -> usize {
+        if self.nodes[node].proof.is_some() || self.nodes.len() >= max_nodes {
+            return node;
+        }
+        let untried = self.nodes[node].untried;
+        if untried == 0 {
+            return node;
+        }
+        let index = (u128::BITS - 1 - untried.leading_zeros()) as usize;
+        self.nodes[node].untried &= !(1 << index);
+        let move_ = index_move(index);
+        let game = apply_move(move_, self.nodes[node].game).expect("invalid move generated");
+        self.nodes.push(Node::new(game, Some(node), Some(move_)));
+        let child = self.nodes.len() - 1;
+        self.nodes[node].children.push(child);
+        child
+    }
+    /// Count a visit without any score for `node` and all of its ancestors, until its playout is done
+    fn add_virtual_loss(&mut self, node: usize) {
+        let mut current = Some(node);
+        while let Some(index) = current {
+            self.nodes[index].visits += 1;
+            current = self.nodes[index].parent;
+        }
+    }
+    /// Add the result of a playout to `node` and all of its ancestors.
+    /// `result` is how the playout ended and `played` the squares taken in the playout.
+    /// With `virtual_loss`, the visits were already counted by [`Mcts::add_virtual_loss`]
+    fn backpropagate(
+        &mut self,
+        node: usize,
+        result: GameState,
+        mut played: Played,
+        virtual_loss: bool,
+    ) {
+        // Proofs can only change on the way up from a proven node, until one doesn't
+        let mut proving = self.config.solver && self.nodes[node].proof.is_some();
+        let mut current = Some(node);
+        while let Some(index) = current {
+            if proving {
+                proving = self.prove(index);
+            }
+            if self.config.rave.is_some() {
+                // Every move of the player to move here that was played below counts as if it was played first
+                let to_move = self.nodes[index].game.current_player;
+                let amaf_reward = reward(result, to_move);
+                for i in 0..self.nodes[index].children.len() {
+                    let child = self.nodes[index].children[i];
+                    let child = &mut self.nodes[child];
+                    if played[player_index(to_move)] & (1 << square_index(child.move_.unwrap()))
+                        != 0
+                    {
+                        child.amaf_visits += 1;
+                        child.amaf_score += amaf_reward;
+                    }
+                }
+            }
+            let node = &mut self.nodes[index];
+            let mover = node.game.current_player.opponent();
+            if !virtual_loss {
+                node.visits += 1;
+            }
+            node.score += reward(result, mover);
+            if let Some(move_) = node.move_ {
+                played[player_index(mover)] |= 1 << square_index(move_);
+            }
+            current = node.parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{play_match, MctsAgent, RandomAgent};
+    use crate::alphabeta::AlphaBeta;
+    use crate::policy::AvoidGifts;
+    use crate::test_positions::{immediate_win, late_positions};
+
+    #[test]
+    fn takes_an_immediate_win() {
+        let game = immediate_win();
+        let mut mcts = Mcts::new(MctsConfig {
+            limits: SearchLimits::iterations(500),
+            ..Default::default()
+        });
+        assert_eq!(mcts.search(game), (0, 2, 0, 2));
+        let win = mcts
+            .root_stats()
+            .into_iter()
+            .find(|stats| stats.move_ == (0, 2, 0, 2))
+            .unwrap();
+        assert_eq!(win.value, 1.0);
+    }
+
+    #[test]
+    fn searches_with_any_policy() {
+        let game = immediate_win();
+        for parallelism in [Parallelism::Root, Parallelism::Tree] {
+            let config = MctsConfig {
+                limits: SearchLimits::iterations(300),
+                threads: 2,
+                parallelism,
+                seed: Some(1),
+                // So the search doesn't stop early once it proves the win
+                solver: false,
+                ..Default::default()
+            };
+            let policy: Arc<dyn RolloutPolicy + Send + Sync> = Arc::new(AvoidGifts);
+            let mut mcts = Mcts::with_policy(config, policy);
+            assert_eq!(mcts.search(game), (0, 2, 0, 2));
+            assert_eq!(mcts.root_visits(), 300);
+        }
+    }
+
+    #[test]
+    fn proves_an_immediate_win() {
+        let game = immediate_win();
+        for (threads, parallelism) in [
+            (1, Parallelism::Tree),
+            (2, Parallelism::Root),
+            (2, Parallelism::Tree),
+        ] {
+            let mut mcts = Mcts::new(MctsConfig {
+                limits: SearchLimits::iterations(10_000),
+                threads,
+                parallelism,
+                ..Default::default()
+            });
+            assert_eq!(mcts.search(game), (0, 2, 0, 2));
+            assert_eq!(mcts.root_proof(), Some(Proof::Loss));
+            assert!(
+                mcts.root_visits() < 10_000,
+                "the search went on after the proof"
+            );
+            let win = mcts
+                .root_stats()
+                .into_iter()
+                .find(|stats| stats.move_ == (0, 2, 0, 2));
+            assert_eq!(win.unwrap().proof, Some(Proof::Win));
+        }
+    }
+
+    #[test]
+    fn agrees_with_alphabeta() {
+        let mut proven = 0;
+        for game in late_positions(12) {
+            let exact = AlphaBeta::new(SearchLimits::iterations(5)).search(game);
+            let mut mcts = Mcts::new(MctsConfig {
+                limits: SearchLimits::iterations(5_000),
+                seed: Some(0),
+                ..Default::default()
+            });
+            let best = mcts.search(game);
+            let position = game.to_position_string();
+            match exact.forced_win_in() {
+                Some(plies) if plies > 0 => {
+                    assert_eq!(mcts.root_proof(), Some(Proof::Loss), "{position}");
+                    // The move played wins, right away or by force
+                    let next = apply_move(best, game).unwrap();
+                    let wins = next.get_winner() == GameState::Winner(game.current_player)
+                        || AlphaBeta::new(SearchLimits::iterations(5))
+                            .search(next)
+                            .forced_win_in()
+                            .is_some_and(|plies| plies < 0);
+                    assert!(wins, "{position}");
+                    proven += 1;
+                }
+                Some(_) => assert_ne!(mcts.root_proof(), Some(Proof::Loss), "{position}"),
+                None => {}
+            }
+            if mcts.root_proof() == Some(Proof::Win) {
+                assert!(
+                    exact.forced_win_in().is_none_or(|plies| plies <= 0),
+                    "{position}"
+                );
+            }
+        }
+        assert!(proven > 0);
+    }
+
+    #[test]
+    fn rave_counts_every_visit() {
+        for rave in [
+            RaveSchedule::Equivalence(300.0),
+            RaveSchedule::MinimumMse(0.1),
+        ] {
+            for (threads, parallelism) in [
+                (1, Parallelism::Tree),
+                (2, Parallelism::Root),
+                (2, Parallelism::Tree),
+            ] {
+                let mut mcts = Mcts::new(MctsConfig {
+                    limits: SearchLimits::iterations(500),
+                    threads,
+                    parallelism,
+                    seed: Some(3),
+                    rave: Some(rave),
+                    ..Default::default()
+                });
+                mcts.search(Game::new());
+                // A visit to a move always counts for its AMAF statistics too
+                for node in &mcts.nodes[1..] {
+                    assert!(node.amaf_visits >= node.visits);
+                }
+                let amaf_visits: u32 = mcts.nodes[0]
+                    .children
+                    .iter()
+                    .map(|&child| mcts.nodes[child].amaf_visits)
+                    .sum();
+                assert!(amaf_visits > mcts.root_visits());
+            }
+        }
+    }
+
+    #[test]
+    fn visits_add_up() {
+        let mut mcts = Mcts::new(MctsConfig {
+            limits: SearchLimits::iterations(300),
+            ..Default::default()
+        });
+        mcts.search(Game::new());
+        let stats = mcts.root_stats();
+        assert_eq!(stats.iter().map(|s| s.visits).sum::<u32>(), 300);
+        assert!(stats.iter().all(|s| (0.0..=1.0).contains(&s.value)));
+        assert_eq!(mcts.tree_size(), 301);
+        // Every legal move got exactly one child
+        let mut moves: Vec<Move> = stats.iter().map(|s| s.move_).collect();
+        moves.sort();
+        let mut legal = Game::new().legal_moves().to_vec();
+        legal.sort();
+        assert_eq!(moves, legal);
+    }
+
+    #[test]
+    fn advancing_keeps_the_subtree() {
+        let mut mcts = Mcts::new(MctsConfig {
+            limits: SearchLimits::iterations(500),
+            ..Default::default()
+        });
+        // Only 8 moves to start with keeps the tree deep enough to look two moves ahead
+        let mut game = Game::new();
+        game.make_move(1, 1, 1, 1).unwrap();
+        let move_ = mcts.search(game);
+        let stats = mcts
+            .root_stats()
+            .into_iter()
+            .find(|stats| stats.move_ == move_)
+            .unwrap();
+        let before = mcts.tree_size();
+        mcts.advance(move_);
+        assert_eq!(mcts.root_visits(), stats.visits);
+        assert!(mcts.tree_size() < before);
+        assert_eq!(
+            mcts.root_stats().iter().map(|s| s.visits).sum::<u32>(),
+            stats.visits - 1
+        );
+
+        // Searching a position two moves deeper reuses its subtree too
+        let reply = mcts.best_move().unwrap();
+        let mut probe = mcts.clone();
+        probe.advance(reply);
+        let counter = probe.best_move().unwrap();
+        let kept = probe
+            .root_stats()
+            .into_iter()
+            .find(|stats| stats.move_ == counter)
+            .unwrap()
+            .visits;
+        let after_counter = [move_, reply, counter]
+            .into_iter()
+            .fold(game, |game, m| apply_move(m, game).unwrap());
+        mcts.search(after_counter);
+        assert_eq!(mcts.root(), Some(after_counter));
+        assert_eq!(mcts.root_visits(), kept + 500);
+    }
+
+    #[test]
+    fn advancing_to_an_unexplored_move_starts_over() {
+        let mut mcts = Mcts::new(MctsConfig {
+            limits: SearchLimits::iterations(10),
+            ..Default::default()
+        });
+        mcts.search(Game::new());
+        let unexplored = *Game::new()
+            .legal_moves()
+            .iter()
+            .find(|&&m| mcts.root_stats().iter().all(|s| s.move_ != m))
+            .unwrap();
+        mcts.advance(unexplored);
+        assert_eq!(mcts.tree_size(), 1);
+        assert_eq!(mcts.root_visits(), 0);
+    }
+
+    #[test]
+    fn stops_at_the_first_limit() {
+        let mut mcts = Mcts::new(MctsConfig {
+            limits: SearchLimits {
+                iterations: Some(1_000),
+                nodes: Some(100),
+                ..Default::default()
+            },
+            reuse_tree: false,
+            ..Default::default()
+        });
+        mcts.search(Game::new());
+        assert_eq!(mcts.tree_size(), 100);
+
+        mcts.config.limits = SearchLimits {
+            memory: Some(50 * NODE_BYTES),
+            ..Default::default()
+        };
+        mcts.search(Game::new());
+        assert_eq!(mcts.tree_size(), 50);
+        assert!(mcts.memory_usage() <= 50 * NODE_BYTES);
+
+        mcts.config.limits = SearchLimits::time(std::time::Duration::from_millis(20));
+        let start = Instant::now();
+        mcts.search(Game::new());
+        assert!(start.elapsed() < std::time::Duration::from_millis(500));
+        assert!(mcts.root_visits() > 0);
+    }
+
+    #[test]
+    fn parallel_visits_add_up() {
+        for parallelism in [Parallelism::Root, Parallelism::Tree] {
+            let mut mcts = Mcts::new(MctsConfig {
+                limits: SearchLimits::iterations(300),
+                threads: 4,
+                parallelism,
+                ..Default::default()
+            });
+            mcts.search(Game::new());
+            assert_eq!(mcts.root_visits(), 300);
+            let stats = mcts.root_stats();
+            assert_eq!(stats.iter().map(|s| s.visits).sum::<u32>(), 300);
+            assert!(stats.iter().all(|s| (0.0..=1.0).contains(&s.value)));
+            let mut moves: Vec<Move> = stats.iter().map(|s| s.move_).collect();
+            moves.sort();
+            moves.dedup();
+            assert_eq!(
+                moves.len(),
+                stats.len(),
+                "{parallelism:?} has duplicate moves"
+            );
+        }
+    }
+
+    #[test]
+    fn root_parallel_stays_within_the_limits() {
+        let mut mcts = Mcts::new(MctsConfig {
+            limits: SearchLimits {
+                iterations: Some(4_000),
+                memory: Some(400 * NODE_BYTES),
+                ..Default::default()
+            },
+            threads: 1,
+            parallelism: Parallelism::Root,
+            ..Default::default()
+        });
+        mcts.search(Game::new());
+        assert_eq!(mcts.tree_size(), 400);
+        // The helpers' trees don't fit next to the full tree that is kept
+        mcts.config.threads = 4;
+        mcts.search(Game::new());
+        assert!(mcts.tree_size() <= 400);
+        assert!(mcts.memory_usage() <= 400 * NODE_BYTES);
+
+        // Fewer iterations than threads doesn't search more than asked
+        mcts.config.limits = SearchLimits::iterations(2);
+        mcts.config.reuse_tree = false;
+        mcts.search(Game::new());
+        assert_eq!(mcts.root_visits(), 2);
+    }
+
+    #[test]
+    fn same_seed_same_search() {
+        for threads in [1, 3] {
+            let search = || {
+                let mut mcts = Mcts::new(MctsConfig {
+                    limits: SearchLimits::iterations(200),
+                    threads,
+                    parallelism: Parallelism::Root,
+                    seed: Some(42),
+                    ..Default::default()
+                });
+                mcts.search(Game::new());
+                mcts.root_stats()
+            };
+            assert_eq!(search(), search());
+        }
+    }
+
+    #[test]
+    fn beats_random() {
+        const GAMES: usize = 8;
+        let wins = play_match(
+            GAMES,
+            |i| {
+                MctsAgent::new(MctsConfig {
+                    limits: SearchLimits::iterations(150),
+                    seed: Some(i as u64),
+                    ..Default::default()
+                })
+            },
+            |i| RandomAgent::new(i as u64),
+        )
+        .wins;
+        assert!(wins >= GAMES - 1, "MCTS only won {wins} of {GAMES} games");
+    }
+}
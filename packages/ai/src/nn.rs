@@ -0,0 +1,413 @@
+//! A small neural network that judges positions for a [PUCT search](crate::puct), in plain Rust on the CPU.
+//!
+//! [`encode`] turns a position into three planes of 81 squares each, in
+//! [square coordinate](super_ttt::to_square_coords) order (`row * 9 + col`), all from the
+//! perspective of the player to move: their pieces, the opponent's pieces, and the squares they
+//! can play in (which takes care of the board they're sent to).
+//!
+//! The [`Network`] is a multilayer perceptron: fully connected hidden layers with ReLU, then an
+//! output layer with a logit for every square (the policy) and one more for the value. The policy
+//! is a softmax over the legal moves only, and the value is squashed by `tanh` to between `-1`
+//! (a certain loss for the player to move) and `1` (a certain win).
+//!
+//! [`Network::train`] fits it to [`Example`]s with stochastic gradient descent, minimizing the
+//! cross-entropy between its policy and the example's, plus the squared error of its value.
+//! A network with a hidden layer or two of a few dozen neurons trains in seconds.
+//!
+//! # File format
+//!
+//! A network file is the 8 byte [`FILE_HEADER`], the number of layers, and then every layer from
+//! the input to the output: its number of inputs and outputs, its weights (one row of inputs
+//! per output) and its biases. Counts are 4 byte and numbers are 4 byte floats, all little-endian.
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use rand::{Rng, RngCore};
+use super_ttt::{Game, GameState, Square, GRID_SIZE};
+
+use crate::puct::{PolicyValue, Prediction};
+use crate::square_index;
+
+/// The number of squares on the board
+pub const SQUARES: usize = GRID_SIZE * GRID_SIZE;
+/// The number of planes [`encode`] makes
+pub const PLANES: usize = 3;
+/// The number of inputs of a [`Network`]
+pub const INPUTS: usize = PLANES * SQUARES;
+/// The number of outputs of a [`Network`]: a policy logit for every square, then the value
+pub const OUTPUTS: usize = SQUARES + 1;
+/// The first bytes of every network file. The last byte is the format version
+pub const FILE_HEADER: [u8; 8] = *b"STTTNET\x01";
+/// Networks this big (in weights per layer) aren't worth running on the CPU, and
+/// a file that claims one is more likely to be corrupt
+const MAX_LAYER_WEIGHTS: usize = 1 << 24;
+
+/// The input planes of `game`. See the [module docs](self)
+pub fn encode(game: &Game) -> [f32; INPUTS] {
+    let mut input = [0.0; INPUTS];
+    for ((row, col), square) in game.iter_squares() {
+        if let Square::Occupied(player) = square {
+            let plane = usize::from(player != game.current_player);
+            input[plane * SQUARES + row * GRID_SIZE + col] = 1.0;
+        }
+    }
+    if game.get_winner() == GameState::InProgress {
+        for &move_ in game.legal_moves().iter() {
+            input[2 * SQUARES + square_index(move_)] = 1.0;
+        }
+    }
+    input
+}
+
+/// A position and what a [`Network`] should say about it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Example {
+    /// The position
+    pub game: Game,
+    /// How likely every move should be, by [`square_index`]. Only legal moves count
+    pub policy: [f32; SQUARES],
+    /// The value for the player to move, from `-1` to `1`. Usually how the game ended
+    pub value: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Layer {
+    inputs: usize,
+    outputs: usize,
+    /// One row of `inputs` weights per output
+    weights: Vec<f32>,
+    biases: Vec<f32>,
+}
+
+impl Layer {
+    fn zeros(inputs: usize, outputs: usize) -> Self {
+        Layer {
+            inputs,
+            outputs,
+            weights: vec![0.0; inputs * outputs],
+            biases: vec![0.0; outputs],
+        }
+    }
+    /// He initialization, which suits ReLU
+    fn random(inputs: usize, outputs: usize, rng: &mut impl Rng) -> Self {
+        let limit = (6.0 / inputs as f32).sqrt();
+        Layer {
+            weights: (0..inputs * outputs)
+                .map(|_| rng.gen_range(-limit..limit))
+                .collect(),
+            ..Layer::zeros(inputs, outputs)
+        }
+    }
+    fn forward(&self, input: &[f32]) -> Vec<f32> {
+        self.weights
+            .chunks_exact(self.inputs)
+            .zip(&self.biases)
+            .map(|(row, bias)| bias + row.iter().zip(input).map(|(w, x)| w * x).sum::<f32>())
+            .collect()
+    }
+}
+
+/// A policy and value network. See the [module docs](self)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    layers: Vec<Layer>,
+}
+
+impl Network {
+    /// A network with hidden layers of the given sizes and random weights drawn from `rng`
+    ///
+    /// # Panics
+    /// Panics if a hidden layer has no neurons
+    pub fn new(hidden: &[usize], rng: &mut impl Rng) -> Self {
+        assert!(
+            hidden.iter().all(|&neurons| neurons > 0),
+            "hidden layers need at least one neuron"
+        );
+        let sizes: Vec<usize> = [INPUTS]
+            .into_iter()
+            .chain(hidden.iter().copied())
+            .chain([OUTPUTS])
+            .collect();
+        Network {
+            layers: sizes
+                .windows(2)
+                .map(|pair| Layer::random(pair[0], pair[1], rng))
+                .collect(),
+        }
+    }
+    /// The sizes of the hidden layers
+    pub fn hidden(&self) -> Vec<usize> {
+        self.layers[1..].iter().map(|layer| layer.inputs).collect()
+    }
+    /// The outputs of every layer, starting with the input itself.
+    /// Hidden layers have gone through ReLU, the output layer hasn't
+    fn forward(&self, input: &[f32]) -> Vec<Vec<f32>> {
+        let mut activations = vec![input.to_vec()];
+        for (i, layer) in self.layers.iter().enumerate() {
+            let mut output = layer.forward(activations.last().unwrap());
+            if i + 1 < self.layers.len() {
+                output.iter_mut().for_each(|x| *x = x.max(0.0));
+            }
+            activations.push(output);
+        }
+        activations
+    }
+    /// The policy over the legal moves (by [`square_index`], `0` elsewhere) and the value
+    /// of `game`, as [`Prediction`] but easier to train on
+    pub fn evaluate(&self, game: &Game) -> ([f32; SQUARES], f32) {
+        let input = encode(game);
+        let output = self.forward(&input).pop().unwrap();
+        (policy(&output, &input), output[SQUARES].tanh())
+    }
+    /// The loss on `example` and its gradient with respect to the outputs
+    fn loss(output: &[f32], input: &[f32], example: &Example) -> (f32, Vec<f32>) {
+        let mut gradient = vec![0.0; OUTPUTS];
+        let policy = policy(output, input);
+        let mut loss = 0.0;
+        for i in 0..SQUARES {
+            if input[2 * SQUARES + i] > 0.0 {
+                let target = example.policy[i];
+                if target > 0.0 {
+                    loss -= target * policy[i].max(f32::MIN_POSITIVE).ln();
+                }
+                // The targets of the legal moves should add up to 1
+                gradient[i] = policy[i] - target;
+            }
+        }
+        let value = output[SQUARES].tanh();
+        loss += (value - example.value).powi(2);
+        gradient[SQUARES] = 2.0 * (value - example.value) * (1.0 - value * value);
+        (loss, gradient)
+    }
+    /// The average loss on `examples`
+    pub fn average_loss(&self, examples: &[Example]) -> f32 {
+        let total: f32 = examples
+            .iter()
+            .map(|example| {
+                let input = encode(&example.game);
+                let output = self.forward(&input).pop().unwrap();
+                Self::loss(&output, &input, example).0
+            })
+            .sum();
+        total / examples.len().max(1) as f32
+    }
+    /// Take one step of gradient descent on the average loss on `examples`, and return that
+    /// loss (from before the step). Call it with small batches of examples, shuffled, many times over
+    pub fn train(&mut self, examples: &[Example], learning_rate: f32) -> f32 {
+        let mut gradients: Vec<Layer> = self
+            .layers
+            .iter()
+            .map(|layer| Layer::zeros(layer.inputs, layer.outputs))
+            .collect();
+        let mut total = 0.0;
+        for example in examples {
+            let input = encode(&example.game);
+            let activations = self.forward(&input);
+            let (loss, mut delta) = Self::loss(activations.last().unwrap(), &input, example);
+            total += loss;
+            for (i, layer) in self.layers.iter().enumerate().rev() {
+                let input = &activations[i];
+                let gradient = &mut gradients[i];
+                for (output, &d) in delta.iter().enumerate() {
+                    gradient.biases[output] += d;
+                    let row = &mut gradient.weights[output * layer.inputs..][..layer.inputs];
+                    for (w, x) in row.iter_mut().zip(input) {
+                        *w += d * x;
+                    }
+                }
+                if i > 0 {
+                    // Through the weights, then through the ReLU of the layer below
+                    let mut previous = vec![0.0; layer.inputs];
+                    for (row, &d) in layer.weights.chunks_exact(layer.inputs).zip(&delta) {
+                        for (p, w) in previous.iter_mut().zip(row) {
+                            *p += w * d;
+                        }
+                    }
+                    for (p, &x) in previous.iter_mut().zip(input) {
+                        if x <= 0.0 {
+                            *p = 0.0;
+                        }
+                    }
+                    delta = previous;
+                }
+            }
+        }
+        let step = learning_rate / examples.len().max(1) as f32;
+        for (layer, gradient) in self.layers.iter_mut().zip(gradients) {
+            for (w, g) in layer.weights.iter_mut().zip(gradient.weights) {
+                *w -= step * g;
+            }
+            for (b, g) in layer.biases.iter_mut().zip(gradient.biases) {
+                *b -= step * g;
+            }
+        }
+        total / examples.len().max(1) as f32
+    }
+
+    /// Write the network in the [file format](self#file-format)
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(&FILE_HEADER)?;
+        writer.write_all(&(self.layers.len() as u32).to_le_bytes())?;
+        for layer in &self.layers {
+            writer.write_all(&(layer.inputs as u32).to_le_bytes())?;
+            writer.write_all(&(layer.outputs as u32).to_le_bytes())?;
+            for number in layer.weights.iter().chain(&layer.biases) {
+                writer.write_all(&number.to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+    /// Read a network in the [file format](self#file-format). A file that doesn't follow it,
+    /// or whose layers don't fit together, is an [`InvalidData`](io::ErrorKind::InvalidData) error
+    pub fn read(mut reader: impl Read) -> io::Result<Self> {
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut header = [0; FILE_HEADER.len()];
+        reader.read_exact(&mut header)?;
+        if header != FILE_HEADER {
+            return Err(invalid("not a super tic tac toe network"));
+        }
+        fn read_u32(reader: &mut impl Read) -> io::Result<usize> {
+            let mut bytes = [0; 4];
+            reader.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes) as usize)
+        }
+        let count = read_u32(&mut reader)?;
+        let mut layers: Vec<Layer> = Vec::new();
+        for _ in 0..count {
+            let inputs = read_u32(&mut reader)?;
+            let outputs = read_u32(&mut reader)?;
+            let expected_inputs = layers.last().map_or(INPUTS, |layer| layer.outputs);
+            if inputs != expected_inputs
+                || outputs == 0
+                || inputs.saturating_mul(outputs) > MAX_LAYER_WEIGHTS
+            {
+                return Err(invalid("the layers of the network don't fit together"));
+            }
+            let mut layer = Layer::zeros(inputs, outputs);
+            for number in layer.weights.iter_mut().chain(&mut layer.biases) {
+                let mut bytes = [0; 4];
+                reader.read_exact(&mut bytes)?;
+                *number = f32::from_le_bytes(bytes);
+            }
+            layers.push(layer);
+        }
+        if layers.last().map(|layer| layer.outputs) != Some(OUTPUTS) {
+            return Err(invalid("the network doesn't have a policy and a value"));
+        }
+        Ok(Network { layers })
+    }
+    /// Write the network to a file that [`Network::load`] can read
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write(BufWriter::new(std::fs::File::create(path)?))
+    }
+    /// Read a network from a file
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read(BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+/// The softmax of the policy logits in `output` over the legal moves of `input`
+fn policy(output: &[f32], input: &[f32]) -> [f32; SQUARES] {
+    let legal = &input[2 * SQUARES..];
+    let max = (0..SQUARES)
+        .filter(|&i| legal[i] > 0.0)
+        .map(|i| output[i])
+        .fold(f32::NEG_INFINITY, f32::max);
+    let mut policy = [0.0; SQUARES];
+    for i in (0..SQUARES).filter(|&i| legal[i] > 0.0) {
+        policy[i] = (output[i] - max).exp();
+    }
+    let total: f32 = policy.iter().sum();
+    if total > 0.0 {
+        policy.iter_mut().for_each(|p| *p /= total);
+    }
+    policy
+}
+
+impl PolicyValue for Network {
+    fn predict(&self, game: &Game, _rng: &mut dyn RngCore) -> Prediction {
+        let (policy, value) = self.evaluate(game);
+        Prediction {
+            priors: game
+                .legal_moves()
+                .iter()
+                .map(|&move_| (move_, policy[square_index(move_)]))
+                .collect(),
+            value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::seeded;
+    use crate::{apply_move, index_move};
+
+    #[test]
+    fn encodes_from_the_movers_perspective() {
+        let game = apply_move((1, 1, 0, 2), Game::new()).unwrap();
+        let input = encode(&game);
+        // O is to move, so X's piece is the opponent's, and O is sent to the top right board
+        assert_eq!(input[SQUARES + 3 * GRID_SIZE + 5], 1.0);
+        assert_eq!(input[..SQUARES].iter().sum::<f32>(), 0.0);
+        assert_eq!(input[2 * SQUARES..].iter().sum::<f32>(), 9.0);
+        assert_eq!(input[2 * SQUARES + square_index((0, 2, 1, 1))], 1.0);
+        assert_eq!(index_move(square_index((0, 2, 1, 1))), (0, 2, 1, 1));
+    }
+
+    #[test]
+    fn learns_a_handful_of_positions() {
+        // The first positions of a game, each with one move to learn and a value
+        let mut examples = Vec::new();
+        let mut game = Game::new();
+        for i in 0..6 {
+            let moves = game.legal_moves();
+            let mut policy = [0.0; SQUARES];
+            policy[square_index(moves[(i * 7 + 3) % moves.len()])] = 1.0;
+            let value = [1.0, -1.0, 0.0][i % 3];
+            examples.push(Example {
+                game,
+                policy,
+                value,
+            });
+            game = apply_move(moves[i % moves.len()], game).unwrap();
+        }
+        let mut network = Network::new(&[24], &mut seeded(0));
+        let before = network.average_loss(&examples);
+        for _ in 0..150 {
+            network.train(&examples, 0.1);
+        }
+        let after = network.average_loss(&examples);
+        assert!(after < before / 4.0, "{before} -> {after}");
+        for example in &examples {
+            let (policy, value) = network.evaluate(&example.game);
+            let best = (0..SQUARES)
+                .max_by(|&a, &b| policy[a].total_cmp(&policy[b]))
+                .unwrap();
+            assert_eq!(example.policy[best], 1.0);
+            assert!((value - example.value).abs() < 0.5);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one neuron")]
+    fn rejects_empty_hidden_layers() {
+        Network::new(&[8, 0], &mut seeded(0));
+    }
+
+    #[test]
+    fn round_trip() {
+        let network = Network::new(&[8, 4], &mut seeded(0));
+        assert_eq!(network.hidden(), [8, 4]);
+        let mut bytes = Vec::new();
+        network.write(&mut bytes).unwrap();
+        assert_eq!(Network::read(&bytes[..]).unwrap(), network);
+        let truncated = Network::read(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(truncated.kind(), io::ErrorKind::UnexpectedEof);
+        // A network for some other input
+        bytes[12] += 1;
+        let mismatched = Network::read(&bytes[..]).unwrap_err();
+        assert_eq!(mismatched.kind(), io::ErrorKind::InvalidData);
+    }
+}
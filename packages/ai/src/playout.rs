@@ -0,0 +1,110 @@
+//! Random playouts that don't allocate.
+//!
+//! [`simulate_game`](crate::simulate_game) keeps every position it passes through, which most
+//! callers throw away. [`playout`] only returns how the game ended and how long it took.
+//! To look at the game along the way, [`playout_with`] calls a visitor before every move,
+//! and [`playout_moves`] writes the moves into a [`MoveList`] (a playout can't be longer than
+//! the 81 squares of the board, so they always fit).
+//!
+//! These play uniformly random moves. [`rollout`] and [`rollout_with`] pick them
+//! with any [`RolloutPolicy`] instead.
+use rand::RngCore;
+use super_ttt::{Game, GameState, Move, MoveList};
+
+use crate::apply_move;
+use crate::policy::{RolloutPolicy, Uniform};
+
+/// How a playout ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Playout {
+    /// The final state of the game. Never [`GameState::InProgress`]
+    pub result: GameState,
+    /// The number of moves played
+    pub length: usize,
+}
+
+impl Playout {
+    /// The result from `X`'s perspective, like [`simulate_game`](crate::simulate_game)'s:
+    /// `1` if `X` won, `-1` if `O` won and `0` for a tie
+    pub fn sign(&self) -> i8 {
+        match self.result {
+            GameState::Winner(player) => player.to_sign(),
+            _ => 0,
+        }
+    }
+}
+
+/// Play random moves, drawn from `rng`, from `game` until the game ends
+pub fn playout(game: Game, rng: &mut impl RngCore) -> Playout {
+    rollout_with(game, &Uniform, rng, |_, _| {})
+}
+
+/// Like [`playout`], but clears `moves` and fills it with the moves that were played
+pub fn playout_moves(game: Game, rng: &mut impl RngCore, moves: &mut MoveList) -> Playout {
+    moves.clear();
+    playout_with(game, rng, |_, move_| moves.push(move_))
+}
+
+/// Like [`playout`], but calls `visit` with every position and the move about to be played in it
+pub fn playout_with(game: Game, rng: &mut impl RngCore, visit: impl FnMut(&Game, Move)) -> Playout {
+    rollout_with(game, &Uniform, rng, visit)
+}
+
+/// Play moves picked by `policy` from `game` until the game ends
+pub fn rollout(
+    game: Game,
+    policy: &(impl RolloutPolicy + ?Sized),
+    rng: &mut impl RngCore,
+) -> Playout {
+    rollout_with(game, policy, rng, |_, _| {})
+}
+
+/// Like [`rollout`], but calls `visit` with every position and the move about to be played in it
+pub fn rollout_with(
+    mut game: Game,
+    policy: &(impl RolloutPolicy + ?Sized),
+    rng: &mut impl RngCore,
+    mut visit: impl FnMut(&Game, Move),
+) -> Playout {
+    let mut legal = MoveList::new();
+    let mut length = 0;
+    loop {
+        let result = game.get_winner();
+        if result != GameState::InProgress {
+            return Playout { result, length };
+        }
+        game.legal_moves_into(&mut legal);
+        let move_ = policy.choose(&game, &legal, rng);
+        visit(&game, move_);
+        game = apply_move(move_, game).expect("invalid move generated");
+        length += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::seeded;
+
+    #[test]
+    fn replaying_the_moves_gives_the_result() {
+        for seed in 0..20 {
+            let mut moves = MoveList::new();
+            let result = playout_moves(Game::new(), &mut seeded(seed), &mut moves);
+            assert_eq!(moves.len(), result.length);
+            let end = moves
+                .iter()
+                .fold(Game::new(), |game, &move_| apply_move(move_, game).unwrap());
+            assert_eq!(end.get_winner(), result.result);
+
+            let mut visited = 0;
+            let same = playout_with(Game::new(), &mut seeded(seed), |game, move_| {
+                assert!(game.legal_moves().contains(&move_));
+                visited += 1;
+            });
+            assert_eq!(same, result);
+            assert_eq!(visited, result.length);
+            assert_eq!(playout(Game::new(), &mut seeded(seed)), result);
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! [Proof-number search](https://www.chessprogramming.org/Proof-Number_Search): solving
+//! positions exactly instead of estimating them.
+//!
+//! Every position gets two numbers for the player to move: `phi`, roughly how many positions
+//! still have to be solved to prove they reach their goal, and `delta`, how many to prove they
+//! don't. A proven position has `phi = 0`, a disproven one `delta = 0`. The player to move only
+//! needs one move that works, so `phi` is the smallest `delta` of the children, while `delta`
+//! needs every move refuted, so it's the sum of the children's `phi`. The search always expands
+//! the position that's cheapest to settle.
+//!
+//! This is the depth-first variant, [df-pn](https://www.chessprogramming.org/DFPN): it only
+//! keeps the current line in memory and stores the numbers of everything else in a
+//! transposition table, staying below a position until its numbers pass a threshold set by
+//! its parent. An overwritten entry is only worked out again, so any table size works.
+//!
+//! Proof-number search answers yes-or-no questions, so [`ProofNumberSearch::solve`] asks two:
+//! whether the player to move can win, and if not, whether they can at least tie.
+//!
+//! For [`SearchLimits`], `nodes` counts the positions searched, `memory` is the size of the
+//! transposition table (16 MiB by default) and iterations aren't used.
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use super_ttt::{Game, GameState, Move, Player};
+
+use crate::limits::SearchLimits;
+use crate::player_index;
+use crate::zobrist::{hash, play};
+
+/// Larger than any proof or disproof number, but small enough that sums don't overflow
+const INFINITY: u32 = u32::MAX / 4;
+const DEFAULT_TABLE_BYTES: usize = 16 << 20;
+
+/// The game-theoretic value of a position for the player to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// They win, whatever the opponent does
+    Win,
+    /// Both sides can force at least a tie
+    Draw,
+    /// They lose, whatever they do
+    Loss,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Outcome::Win => "win",
+            Outcome::Draw => "draw",
+            Outcome::Loss => "loss",
+        })
+    }
+}
+
+/// What [`ProofNumberSearch::solve`] proved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Solution {
+    /// The value of the position for the player to move
+    pub outcome: Outcome,
+    /// A move that keeps the outcome: a winning move for a win, a move that holds the tie for a draw.
+    /// `None` for a loss, since every move loses, and for finished games
+    pub proof_move: Option<Move>,
+    /// Positions searched
+    pub nodes: u64,
+    /// How long the search took
+    pub time: Duration,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Entry {
+    key: u64,
+    phi: u32,
+    delta: u32,
+}
+
+/// A df-pn solver. See the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct ProofNumberSearch {
+    limits: SearchLimits,
+    table: Vec<Entry>,
+    nodes: u64,
+    start: Instant,
+    stopped: bool,
+    /// The player whose goal is being proven
+    attacker: Player,
+    /// Whether a tie is enough for the attacker
+    tie_is_enough: bool,
+    /// The move at the root with the smallest `delta` after the last search
+    root_best: Option<Move>,
+}
+
+impl ProofNumberSearch {
+    /// Create a solver that searches within `limits`
+    pub fn new(limits: SearchLimits) -> Self {
+        let table_bytes = limits.memory.unwrap_or(DEFAULT_TABLE_BYTES);
+        // A power of two, so the index is just the low bits of the hash
+        let entries = (table_bytes / std::mem::size_of::<Entry>()).max(2);
+        let entries = 1 << entries.ilog2();
+        ProofNumberSearch {
+            limits,
+            table: vec![Entry::default(); entries],
+            nodes: 0,
+            start: Instant::now(),
+            stopped: false,
+            attacker: Player::X,
+            tie_is_enough: false,
+            root_best: None,
+        }
+    }
+    /// The limits this solver searches within
+    pub fn limits(&self) -> &SearchLimits {
+        &self.limits
+    }
+    /// Forget everything in the transposition table
+    pub fn clear(&mut self) {
+        self.table.fill(Entry::default());
+    }
+    /// Find the exact value of `game` for the player to move, or `None` if one of the
+    /// limits is reached first. Whatever was learned is kept for the next search
+    pub fn solve(&mut self, game: Game) -> Option<Solution> {
+        self.start = Instant::now();
+        self.nodes = 0;
+        self.stopped = false;
+        let (outcome, proof_move) = if self.prove(game, false)? {
+            (Outcome::Win, self.root_best)
+        } else if self.prove(game, true)? {
+            (Outcome::Draw, self.root_best)
+        } else {
+            (Outcome::Loss, None)
+        };
+        Some(Solution {
+            outcome,
+            proof_move: proof_move.filter(|_| game.get_winner() == GameState::InProgress),
+            nodes: self.nodes,
+            time: self.start.elapsed(),
+        })
+    }
+
+    /// Whether the player to move can win (or tie, with `tie_is_enough`), if the search gets that far
+    fn prove(&mut self, game: Game, tie_is_enough: bool) -> Option<bool> {
+        self.attacker = game.current_player;
+        self.tie_is_enough = tie_is_enough;
+        self.root_best = None;
+        let (phi, delta) = self.mid(&game, hash(&game), INFINITY, INFINITY, true);
+        match (phi, delta) {
+            (0, _) => Some(true),
+            (_, 0) => Some(false),
+            _ => None,
+        }
+    }
+    /// Search `game` until its `phi` or `delta` reaches its threshold, and return them
+    fn mid(
+        &mut self,
+        game: &Game,
+        hash: u64,
+        max_phi: u32,
+        max_delta: u32,
+        root: bool,
+    ) -> (u32, u32) {
+        self.nodes += 1;
+        // `u64::is_multiple_of` would need Rust 1.87
+        #[allow(clippy::manual_is_multiple_of)]
+        let out_of_time =
+            self.nodes % 1024 == 0 && self.limits.time.is_some_and(|t| self.start.elapsed() >= t);
+        if out_of_time
+            || self
+                .limits
+                .nodes
+                .is_some_and(|nodes| self.nodes >= nodes as u64)
+        {
+            self.stopped = true;
+        }
+        if let Some(numbers) = self.finished(game) {
+            self.store(hash, numbers);
+            return numbers;
+        }
+        let mut children: Vec<_> = game
+            .legal_moves()
+            .iter()
+            .map(|&move_| {
+                let (child, child_hash) = play(game, hash, move_);
+                let numbers = self
+                    .finished(&child)
+                    .unwrap_or_else(|| self.lookup(child_hash));
+                (move_, child, child_hash, numbers)
+            })
+            .collect();
+        loop {
+            let mut phi = INFINITY;
+            let mut delta: u32 = 0;
+            let (mut best, mut second_delta) = (0, INFINITY);
+            for (i, &(_, _, _, (child_phi, child_delta))) in children.iter().enumerate() {
+                delta = (delta + child_phi).min(INFINITY);
+                if child_delta < phi {
+                    second_delta = phi;
+                    phi = child_delta;
+                    best = i;
+                } else if child_delta < second_delta {
+                    second_delta = child_delta;
+                }
+            }
+            if root {
+                self.root_best = Some(children[best].0);
+            }
+            if phi >= max_phi || delta >= max_delta || self.stopped {
+                self.store(hash, (phi, delta));
+                return (phi, delta);
+            }
+            let (_, child, child_hash, (child_phi, _)) = children[best];
+            // Enough that this position's thresholds are reached when the child's are
+            let child_max_phi = (max_delta - delta + child_phi).min(INFINITY);
+            let child_max_delta = max_phi.min(second_delta + 1);
+            children[best].3 = self.mid(&child, child_hash, child_max_phi, child_max_delta, false);
+        }
+    }
+    /// The numbers of a finished game, or `None` if it's still in progress
+    fn finished(&self, game: &Game) -> Option<(u32, u32)> {
+        let attacker_succeeds = match game.get_winner() {
+            GameState::InProgress => return None,
+            GameState::Winner(winner) => winner == self.attacker,
+            GameState::Tie => self.tie_is_enough,
+        };
+        Some(
+            if (game.current_player == self.attacker) == attacker_succeeds {
+                (0, INFINITY)
+            } else {
+                (INFINITY, 0)
+            },
+        )
+    }
+    /// Entries of the two questions for both players can't be mixed up
+    fn key(&self, hash: u64) -> u64 {
+        let question = 2 * player_index(self.attacker) + self.tie_is_enough as usize;
+        hash ^ 0x9e37_79b9_7f4a_7c15_u64.wrapping_mul(question as u64 + 1)
+    }
+    fn lookup(&self, hash: u64) -> (u32, u32) {
+        let key = self.key(hash);
+        let entry = self.table[key as usize & (self.table.len() - 1)];
+        if entry.key == key {
+            (entry.phi, entry.delta)
+        } else {
+            (1, 1)
+        }
+    }
+    fn store(&mut self, hash: u64, (phi, delta): (u32, u32)) {
+        let key = self.key(hash);
+        let slot = key as usize & (self.table.len() - 1);
+        self.table[slot] = Entry { key, phi, delta };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabeta::AlphaBeta;
+    use crate::apply_move;
+    use crate::test_positions::{immediate_win, late_positions};
+
+    #[test]
+    fn proves_an_immediate_win() {
+        let game = immediate_win();
+        // Even a tiny table is enough, it just takes longer
+        for memory in [None, Some(1 << 10)] {
+            let mut solver = ProofNumberSearch::new(SearchLimits {
+                memory,
+                ..Default::default()
+            });
+            let solution = solver.solve(game).unwrap();
+            assert_eq!(solution.outcome, Outcome::Win);
+            assert_eq!(solution.proof_move, Some((0, 2, 0, 2)));
+        }
+    }
+
+    #[test]
+    fn agrees_with_alphabeta() {
+        let mut solver = ProofNumberSearch::new(SearchLimits {
+            nodes: Some(20_000),
+            ..Default::default()
+        });
+        let mut solved = 0;
+        for game in late_positions(10) {
+            let position = game.to_position_string();
+            let Some(solution) = solver.solve(game) else {
+                continue;
+            };
+            solved += 1;
+            let expected = match AlphaBeta::new(SearchLimits::iterations(5))
+                .search(game)
+                .forced_win_in()
+            {
+                Some(plies) if plies > 0 => Some(Outcome::Win),
+                Some(_) => Some(Outcome::Loss),
+                None => None,
+            };
+            if let Some(expected) = expected {
+                assert_eq!(solution.outcome, expected, "{position}");
+            }
+            // The proof move keeps the outcome
+            if let Some(move_) = solution.proof_move {
+                let next = apply_move(move_, game).unwrap();
+                let reply = match next.get_winner() {
+                    GameState::Winner(_) => Outcome::Loss,
+                    GameState::Tie => Outcome::Draw,
+                    GameState::InProgress => match solver.solve(next) {
+                        Some(reply) => reply.outcome,
+                        None => continue,
+                    },
+                };
+                let kept = match solution.outcome {
+                    Outcome::Win => Outcome::Loss,
+                    _ => Outcome::Draw,
+                };
+                assert_eq!(reply, kept, "{position}");
+            }
+        }
+        assert!(solved >= 5, "only {solved} positions solved");
+    }
+
+    #[test]
+    fn stops_at_the_node_limit() {
+        let mut solver = ProofNumberSearch::new(SearchLimits {
+            nodes: Some(1_000),
+            ..Default::default()
+        });
+        assert_eq!(solver.solve(Game::new()), None);
+        assert_eq!(solver.nodes, 1_000);
+    }
+}
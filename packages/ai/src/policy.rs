@@ -0,0 +1,254 @@
+//! Rollout policies: how the moves of a [playout](crate::playout) are picked.
+//!
+//! Uniformly random playouts are cheap, but they're full of blunders no player would make,
+//! like leaving a board the opponent can win in one move, so the results they give
+//! [MCTS](crate::mcts) are noisy. A [`RolloutPolicy`] can play a little smarter, at the cost
+//! of fewer playouts per second:
+//!
+//! | Policy               | Plays                                                                      |
+//! |----------------------|----------------------------------------------------------------------------|
+//! | [`Uniform`]          | Any legal move                                                             |
+//! | [`WinIfPossible`]    | A move that wins a small board if there is one, otherwise any              |
+//! | [`WinOrBlock`]       | Like [`WinIfPossible`], but also blocks the opponent from winning a board  |
+//! | [`AvoidGifts`]       | Like [`WinOrBlock`], but avoids sending the opponent to a board they can win |
+//! | [`EpsilonGreedy`]    | Usually the move an [`Evaluator`] likes best, sometimes any                |
+//!
+//! Whenever a policy has several equally good moves, it picks one of them at random.
+//! The threats come straight from the [lookup table](super_ttt::lookup), so the tactical
+//! policies stay cheap.
+use std::sync::Arc;
+
+use rand::{Rng, RngCore};
+use super_ttt::{Game, GameState, Move, MoveList, Player, Square};
+
+use crate::apply_move;
+use crate::eval::{Evaluator, HandcraftedEvaluator};
+
+/// Picks the moves of a playout
+pub trait RolloutPolicy {
+    /// Pick one of `moves`, the legal moves of `game`, which are never empty
+    fn choose(&self, game: &Game, moves: &MoveList, rng: &mut dyn RngCore) -> Move;
+}
+
+impl<P: RolloutPolicy + ?Sized> RolloutPolicy for Box<P> {
+    fn choose(&self, game: &Game, moves: &MoveList, rng: &mut dyn RngCore) -> Move {
+        (**self).choose(game, moves, rng)
+    }
+}
+
+/// Lets a policy picked at runtime be shared between the threads of a search
+impl<P: RolloutPolicy + ?Sized> RolloutPolicy for Arc<P> {
+    fn choose(&self, game: &Game, moves: &MoveList, rng: &mut dyn RngCore) -> Move {
+        (**self).choose(game, moves, rng)
+    }
+}
+
+/// Picks any legal move, uniformly at random
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Uniform;
+
+impl RolloutPolicy for Uniform {
+    fn choose(&self, _game: &Game, moves: &MoveList, rng: &mut dyn RngCore) -> Move {
+        moves[rng.gen_range(0..moves.len())]
+    }
+}
+
+/// Wins a small board whenever it can, otherwise plays like [`Uniform`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WinIfPossible;
+
+impl RolloutPolicy for WinIfPossible {
+    fn choose(&self, game: &Game, moves: &MoveList, rng: &mut dyn RngCore) -> Move {
+        let me = game.current_player;
+        pick(moves, rng, |move_| wins_board(game, move_, me))
+            .unwrap_or_else(|| Uniform.choose(game, moves, rng))
+    }
+}
+
+/// Wins a small board whenever it can, otherwise blocks the opponent from winning one
+/// if it can, otherwise plays like [`Uniform`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WinOrBlock;
+
+impl RolloutPolicy for WinOrBlock {
+    fn choose(&self, game: &Game, moves: &MoveList, rng: &mut dyn RngCore) -> Move {
+        let me = game.current_player;
+        pick(moves, rng, |move_| wins_board(game, move_, me))
+            .or_else(|| pick(moves, rng, |move_| wins_board(game, move_, me.opponent())))
+            .unwrap_or_else(|| Uniform.choose(game, moves, rng))
+    }
+}
+
+/// Plays like [`WinOrBlock`], but otherwise avoids moves that let the opponent win a board
+/// straight away: sending them to a board they can win, or to a finished board while
+/// they can win any board
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AvoidGifts;
+
+impl RolloutPolicy for AvoidGifts {
+    fn choose(&self, game: &Game, moves: &MoveList, rng: &mut dyn RngCore) -> Move {
+        let me = game.current_player;
+        pick(moves, rng, |move_| wins_board(game, move_, me))
+            .or_else(|| pick(moves, rng, |move_| wins_board(game, move_, me.opponent())))
+            .or_else(|| pick(moves, rng, |move_| !gives_a_board(game, move_)))
+            .unwrap_or_else(|| Uniform.choose(game, moves, rng))
+    }
+}
+
+/// With probability `epsilon` plays like [`Uniform`], otherwise plays the move after which
+/// the evaluator likes the position best. A move that wins the game is always played
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpsilonGreedy<E = HandcraftedEvaluator> {
+    /// Judges the position after every move
+    pub evaluator: E,
+    /// How often to play a random move instead, from `0` to `1`
+    pub epsilon: f64,
+}
+
+impl EpsilonGreedy {
+    /// Play a random move with probability `epsilon`, or the best one by the default
+    /// [`HandcraftedEvaluator`]
+    pub fn new(epsilon: f64) -> Self {
+        Self::with_evaluator(HandcraftedEvaluator::default(), epsilon)
+    }
+}
+
+impl<E: Evaluator> EpsilonGreedy<E> {
+    /// Play a random move with probability `epsilon`, or the best one by `evaluator`
+    pub fn with_evaluator(evaluator: E, epsilon: f64) -> Self {
+        EpsilonGreedy { evaluator, epsilon }
+    }
+}
+
+impl<E: Evaluator> RolloutPolicy for EpsilonGreedy<E> {
+    fn choose(&self, game: &Game, moves: &MoveList, rng: &mut dyn RngCore) -> Move {
+        if rng.gen_bool(self.epsilon.clamp(0.0, 1.0)) {
+            return Uniform.choose(game, moves, rng);
+        }
+        let mut best = MoveList::new();
+        let mut best_score = i32::MIN;
+        for &move_ in moves.iter() {
+            let next = apply_move(move_, *game).expect("invalid move generated");
+            let score = match next.get_winner() {
+                GameState::Winner(_) => return move_,
+                GameState::Tie => 0,
+                // The evaluation is from the opponent's perspective now
+                GameState::InProgress => -self.evaluator.evaluate(&next),
+            };
+            if score > best_score {
+                best_score = score;
+                best.clear();
+            }
+            if score == best_score {
+                best.push(move_);
+            }
+        }
+        best[rng.gen_range(0..best.len())]
+    }
+}
+
+/// The bit of a move's square in the [lookup table](super_ttt::lookup)'s masks
+fn square_bit(move_: Move) -> u16 {
+    let (_, _, row, col) = move_;
+    1 << (row * 3 + col)
+}
+
+/// Whether `player` would win the small board `move_` is played on by playing there
+fn wins_board(game: &Game, move_: Move, player: Player) -> bool {
+    let (board_row, board_col, _, _) = move_;
+    game.boards[board_row][board_col]
+        .info()
+        .winning_squares(player)
+        & square_bit(move_)
+        != 0
+}
+
+/// Whether the opponent can win a small board right after `move_`
+fn gives_a_board(game: &Game, move_: Move) -> bool {
+    let (board_row, board_col, row, col) = move_;
+    let opponent = game.current_player.opponent();
+    let mut boards = game.boards;
+    boards[board_row][board_col].squares[row][col] = Square::Occupied(game.current_player);
+    let target = boards[row][col].info();
+    if target.state == GameState::InProgress {
+        target.can_win(opponent)
+    } else {
+        // They may play anywhere. Finished boards have no winning squares
+        boards
+            .iter()
+            .flatten()
+            .any(|board| board.info().can_win(opponent))
+    }
+}
+
+/// One of the moves that pass `filter`, at random
+fn pick(moves: &MoveList, rng: &mut dyn RngCore, filter: impl Fn(Move) -> bool) -> Option<Move> {
+    let mut passed = MoveList::new();
+    for &move_ in moves.iter() {
+        if filter(move_) {
+            passed.push(move_);
+        }
+    }
+    (!passed.is_empty()).then(|| passed[rng.gen_range(0..passed.len())])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::seeded;
+
+    /// X to move in the top left board, with the given pieces in it
+    fn top_left(x: &[(usize, usize)], o: &[(usize, usize)]) -> Game {
+        let mut game = Game::new();
+        for (&(row, col), player) in x
+            .iter()
+            .map(|square| (square, Player::X))
+            .chain(o.iter().map(|square| (square, Player::O)))
+        {
+            game.boards[0][0].squares[row][col] = Square::Occupied(player);
+        }
+        game.last_move_cords = Some((0, 0));
+        game
+    }
+
+    /// Every move `policy` plays in `game` over a few seeds
+    fn choices(policy: &dyn RolloutPolicy, game: &Game) -> Vec<Move> {
+        let moves = game.legal_moves();
+        (0..50)
+            .map(|seed| policy.choose(game, &moves, &mut seeded(seed)))
+            .collect()
+    }
+
+    #[test]
+    fn wins_and_blocks() {
+        // Both sides threaten to win the top left board, and X is to move
+        let game = top_left(&[(0, 0), (0, 1)], &[(1, 0), (1, 1)]);
+        let greedy = EpsilonGreedy::new(0.0);
+        let policies: [&dyn RolloutPolicy; 4] = [&WinIfPossible, &WinOrBlock, &AvoidGifts, &greedy];
+        for policy in policies {
+            assert!(choices(policy, &game).iter().all(|&m| m == (0, 0, 0, 2)));
+        }
+        assert!(choices(&Uniform, &game).iter().any(|&m| m != (0, 0, 0, 2)));
+
+        // Only O threatens
+        let game = top_left(&[(2, 2)], &[(1, 0), (1, 1)]);
+        assert!(choices(&WinOrBlock, &game)
+            .iter()
+            .all(|&m| m == (0, 0, 1, 2)));
+        assert!(choices(&WinIfPossible, &game)
+            .iter()
+            .any(|&m| m != (0, 0, 1, 2)));
+    }
+
+    #[test]
+    fn avoids_gifts() {
+        // O can win the bottom right board, so X shouldn't send them there
+        let mut game = top_left(&[], &[]);
+        game.boards[2][2].squares[0][0] = Square::Occupied(Player::O);
+        game.boards[2][2].squares[0][1] = Square::Occupied(Player::O);
+        assert!(choices(&AvoidGifts, &game)
+            .iter()
+            .all(|&m| m != (0, 0, 2, 2)));
+        assert!(choices(&WinOrBlock, &game).contains(&(0, 0, 2, 2)));
+    }
+}
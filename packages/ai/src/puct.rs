@@ -0,0 +1,426 @@
+//! PUCT search: Monte Carlo Tree Search guided by a policy and a value, like
+//! [AlphaZero](https://en.wikipedia.org/wiki/AlphaZero)'s.
+//!
+//! Instead of playing a new leaf out, the search asks a [`PolicyValue`] (usually a
+//! [neural network](crate::nn::Network)) about it: how likely each move is to be the best one
+//! (its *prior*), and how good the position is. The value is backed up the tree like a playout
+//! result, and selection picks the child with the highest
+//!
+//! ```text
+//! Q + c · P · √N / (1 + n)
+//! ```
+//!
+//! where `Q` is the child's average value, `P` its prior, `N` the parent's visits, `n` the
+//! child's visits and `c` the [exploration constant](PuctConfig::exploration). So the moves the
+//! priors like get searched first, and the values decide which of them hold up. A node gets all
+//! of its children at once, when it's first visited, and unvisited children count as a `Q` of `0`.
+//!
+//! Values go from `-1` (a certain loss) to `1` (a certain win), from the perspective of the
+//! player who made the move leading into a node, and finished games are scored exactly.
+//! Every search starts from scratch on a single thread.
+use std::sync::Arc;
+use std::time::Instant;
+
+use rand::RngCore;
+use super_ttt::{Game, GameState, Move};
+
+use crate::apply_move;
+use crate::limits::SearchLimits;
+use crate::playout::playout;
+use crate::rng::{self, GameRng};
+
+/// What a [`PolicyValue`] thinks of a position
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prediction {
+    /// The prior of every legal move. They should add up to `1`; a [`Puct`] search normalizes
+    /// them anyway, and gives moves that are left out a prior of `0`
+    pub priors: Vec<(Move, f32)>,
+    /// How good the position is for the player to move, from `-1` to `1`
+    pub value: f32,
+}
+
+/// Judges positions for a [`Puct`] search. Only asked about games in progress
+pub trait PolicyValue {
+    /// The priors and value of `game`. `rng` is there for evaluators that need randomness
+    fn predict(&self, game: &Game, rng: &mut dyn RngCore) -> Prediction;
+}
+
+impl<E: PolicyValue + ?Sized> PolicyValue for &E {
+    fn predict(&self, game: &Game, rng: &mut dyn RngCore) -> Prediction {
+        (**self).predict(game, rng)
+    }
+}
+impl<E: PolicyValue + ?Sized> PolicyValue for Box<E> {
+    fn predict(&self, game: &Game, rng: &mut dyn RngCore) -> Prediction {
+        (**self).predict(game, rng)
+    }
+}
+impl<E: PolicyValue + ?Sized> PolicyValue for Arc<E> {
+    fn predict(&self, game: &Game, rng: &mut dyn RngCore) -> Prediction {
+        (**self).predict(game, rng)
+    }
+}
+
+/// The same prior for every move, and a random playout for the value. PUCT with these is
+/// close to plain MCTS, which makes it a baseline before there's a network to search with
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rollouts;
+
+impl PolicyValue for Rollouts {
+    fn predict(&self, game: &Game, mut rng: &mut dyn RngCore) -> Prediction {
+        let moves = game.legal_moves();
+        let prior = 1.0 / moves.len() as f32;
+        let value = match playout(*game, &mut rng).result {
+            GameState::Winner(winner) if winner == game.current_player => 1.0,
+            GameState::Winner(_) => -1.0,
+            _ => 0.0,
+        };
+        Prediction {
+            priors: moves.iter().map(|&move_| (move_, prior)).collect(),
+            value,
+        }
+    }
+}
+
+/// How a [`Puct`] searches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PuctConfig {
+    /// When to stop searching. Iterations are positions judged
+    pub limits: SearchLimits,
+    /// The exploration constant `c`. Higher values trust the priors more,
+    /// lower values trust the values so far. Defaults to `1.5`
+    pub exploration: f64,
+    /// Mix random noise into the priors at the root, so the search looks at moves the priors
+    /// would rule out. For self-play, where games should vary. `None` by default
+    pub noise: Option<DirichletNoise>,
+    /// The seed for the random numbers of the noise and the [`PolicyValue`]. `None` seeds from the operating system
+    pub seed: Option<u64>,
+}
+
+/// [`PuctConfig::noise`]: every prior at the root becomes `(1 - fraction) * prior + fraction * noise`,
+/// where the noise is drawn from a [Dirichlet distribution](crate::rng::dirichlet) with concentration `alpha`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirichletNoise {
+    /// The smaller it is, the more the noise piles up on a few moves. Defaults to `0.3`
+    pub alpha: f64,
+    /// How much of every prior is noise. Defaults to `0.25`
+    pub fraction: f64,
+}
+
+impl Default for DirichletNoise {
+    fn default() -> Self {
+        DirichletNoise {
+            alpha: 0.3,
+            fraction: 0.25,
+        }
+    }
+}
+
+impl Default for PuctConfig {
+    fn default() -> Self {
+        PuctConfig {
+            limits: SearchLimits::iterations(800),
+            exploration: 1.5,
+            noise: None,
+            seed: None,
+        }
+    }
+}
+
+/// What the search found out about a move from the root
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PuctStats {
+    /// The move
+    pub move_: Move,
+    /// How many times the search went through this move
+    pub visits: u32,
+    /// The average value for the player making the move, from `-1` to `1`
+    pub value: f64,
+    /// The move's prior
+    pub prior: f32,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    game: Game,
+    parent: Option<usize>,
+    /// The move that led from the parent to this node
+    move_: Option<Move>,
+    prior: f32,
+    children: Vec<usize>,
+    visits: u32,
+    /// Sum of the values, from the perspective of the player who moved into this node
+    value: f64,
+}
+
+/// Roughly how much memory a node takes: itself plus its index in its parent's `children`
+const NODE_BYTES: usize = std::mem::size_of::<Node>() + std::mem::size_of::<usize>();
+
+impl Node {
+    fn new(game: Game, parent: Option<usize>, move_: Option<Move>, prior: f32) -> Self {
+        Node {
+            game,
+            parent,
+            move_,
+            prior,
+            children: Vec::new(),
+            visits: 0,
+            value: 0.0,
+        }
+    }
+    fn q(&self) -> f64 {
+        if self.visits == 0 {
+            0.0
+        } else {
+            self.value / self.visits as f64
+        }
+    }
+}
+
+/// A PUCT search engine that judges positions with `E`. See the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct Puct<E> {
+    config: PuctConfig,
+    evaluator: E,
+    /// Every node of the tree. The root is always at index 0
+    nodes: Vec<Node>,
+    rng: GameRng,
+}
+
+impl<E: PolicyValue> Puct<E> {
+    /// Create an engine that searches according to `config` and judges positions with `evaluator`
+    pub fn new(config: PuctConfig, evaluator: E) -> Self {
+        Puct {
+            config,
+            evaluator,
+            nodes: Vec::new(),
+            rng: config.seed.map_or_else(rng::from_entropy, rng::seeded),
+        }
+    }
+    /// The configuration this engine searches with
+    pub fn config(&self) -> &PuctConfig {
+        &self.config
+    }
+    /// What judges the positions
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+    /// Search `root` until one of the [limits](PuctConfig::limits) is reached and return
+    /// the most visited move.
+    ///
+    /// # Panics
+    /// Panics if the game is already over or the search has no limits at all
+    pub fn search(&mut self, root: Game) -> Move {
+        assert_eq!(
+            root.get_winner(),
+            GameState::InProgress,
+            "can't search a finished game"
+        );
+        assert!(
+            !self.config.limits.is_unlimited(),
+            "the search needs at least one limit"
+        );
+        let start = Instant::now();
+        self.nodes.clear();
+        self.nodes.push(Node::new(root, None, None, 1.0));
+        let mut iterations = 0;
+        while iterations == 0
+            || !self
+                .config
+                .limits
+                .reached(start, iterations, self.nodes.len(), NODE_BYTES)
+        {
+            let leaf = self.select();
+            let value = self.evaluate(leaf);
+            self.backpropagate(leaf, value);
+            iterations += 1;
+        }
+        self.best_move()
+            .expect("the root has children after a search")
+    }
+    /// Statistics for every move from the root, after a [`Puct::search`]
+    pub fn root_stats(&self) -> Vec<PuctStats> {
+        self.nodes.first().map_or_else(Vec::new, |root| {
+            root.children
+                .iter()
+                .map(|&child| {
+                    let child = &self.nodes[child];
+                    PuctStats {
+                        move_: child.move_.unwrap(),
+                        visits: child.visits,
+                        value: child.q(),
+                        prior: child.prior,
+                    }
+                })
+                .collect()
+        })
+    }
+    /// The most visited move from the root, or `None` if nothing has been searched
+    pub fn best_move(&self) -> Option<Move> {
+        self.root_stats()
+            .into_iter()
+            .max_by(|a, b| a.visits.cmp(&b.visits).then(a.prior.total_cmp(&b.prior)))
+            .map(|stats| stats.move_)
+    }
+    /// How many times the root has been visited
+    pub fn root_visits(&self) -> u32 {
+        self.nodes.first().map_or(0, |root| root.visits)
+    }
+    /// The number of nodes in the search tree
+    pub fn tree_size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Descend from the root to a node that hasn't been visited yet (or a finished game)
+    fn select(&self) -> usize {
+        let mut current = 0;
+        while !self.nodes[current].children.is_empty() {
+            let node = &self.nodes[current];
+            let sqrt_visits = (node.visits as f64).sqrt();
+            let score = |child: usize| {
+                let child = &self.nodes[child];
+                child.q()
+                    + self.config.exploration * child.prior as f64 * sqrt_visits
+                        / (1 + child.visits) as f64
+            };
+            current = *node
+                .children
+                .iter()
+                .max_by(|&&a, &&b| score(a).total_cmp(&score(b)))
+                .unwrap();
+        }
+        current
+    }
+    /// The value of `node` for the player to move there, adding its children if the game isn't over
+    fn evaluate(&mut self, node: usize) -> f64 {
+        let game = self.nodes[node].game;
+        match game.get_winner() {
+            // Only the player who just moved can have won
+            GameState::Winner(_) => return -1.0,
+            GameState::Tie => return 0.0,
+            GameState::InProgress => {}
+        }
+        let prediction = self.evaluator.predict(&game, &mut self.rng);
+        let moves = game.legal_moves();
+        let mut priors: Vec<f32> = moves
+            .iter()
+            .map(|move_| {
+                prediction
+                    .priors
+                    .iter()
+                    .find(|(prior_move, _)| prior_move == move_)
+                    .map_or(0.0, |&(_, prior)| prior.max(0.0))
+            })
+            .collect();
+        let total: f32 = priors.iter().sum();
+        for prior in &mut priors {
+            *prior = if total > 0.0 {
+                *prior / total
+            } else {
+                1.0 / moves.len() as f32
+            };
+        }
+        if let Some(noise) = self.config.noise.filter(|_| node == 0) {
+            let sample = rng::dirichlet(noise.alpha, priors.len(), &mut self.rng);
+            for (prior, x) in priors.iter_mut().zip(sample) {
+                *prior = (1.0 - noise.fraction as f32) * *prior + noise.fraction as f32 * x as f32;
+            }
+        }
+        for (&move_, prior) in moves.iter().zip(priors) {
+            let child = apply_move(move_, game).expect("invalid move generated");
+            self.nodes
+                .push(Node::new(child, Some(node), Some(move_), prior));
+            let child = self.nodes.len() - 1;
+            self.nodes[node].children.push(child);
+        }
+        prediction.value as f64
+    }
+    /// Add `value`, for the player to move at `node`, to `node` and all of its ancestors
+    fn backpropagate(&mut self, node: usize, value: f64) {
+        // Every node keeps the value for the player who moved into it
+        let mut value = -value;
+        let mut current = Some(node);
+        while let Some(index) = current {
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            node.value += value;
+            value = -value;
+            current = node.parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_positions::immediate_win;
+
+    /// Puts all of its prior on one move and thinks every position is even
+    struct Favourite(Move);
+
+    impl PolicyValue for Favourite {
+        fn predict(&self, _game: &Game, _rng: &mut dyn RngCore) -> Prediction {
+            // Where the move isn't legal, the search falls back to the same prior for every move
+            Prediction {
+                priors: vec![(self.0, 1.0)],
+                value: 0.0,
+            }
+        }
+    }
+
+    #[test]
+    fn takes_an_immediate_win() {
+        let game = immediate_win();
+        let mut puct = Puct::new(
+            PuctConfig {
+                limits: SearchLimits::iterations(300),
+                seed: Some(0),
+                ..Default::default()
+            },
+            Rollouts,
+        );
+        assert_eq!(puct.search(game), (0, 2, 0, 2));
+    }
+
+    #[test]
+    fn follows_the_priors() {
+        let mut puct = Puct::new(
+            PuctConfig {
+                limits: SearchLimits::iterations(50),
+                seed: Some(0),
+                ..Default::default()
+            },
+            Favourite((2, 1, 0, 2)),
+        );
+        assert_eq!(puct.search(Game::new()), (2, 1, 0, 2));
+        // Every iteration but the first went through a move
+        let visits: u32 = puct.root_stats().iter().map(|stats| stats.visits).sum();
+        assert_eq!(visits + 1, puct.root_visits());
+        assert_eq!(puct.root_visits(), 50);
+    }
+
+    #[test]
+    fn noise_changes_the_root_priors() {
+        let config = PuctConfig {
+            limits: SearchLimits::iterations(10),
+            seed: Some(0),
+            ..Default::default()
+        };
+        let priors = |config| {
+            let mut puct = Puct::new(config, Rollouts);
+            puct.search(Game::new());
+            puct.root_stats()
+                .iter()
+                .map(|stats| stats.prior)
+                .collect::<Vec<_>>()
+        };
+        let plain = priors(config);
+        let noisy = priors(PuctConfig {
+            noise: Some(DirichletNoise::default()),
+            ..config
+        });
+        assert!(plain.iter().all(|prior| (prior - 1.0 / 81.0).abs() < 1e-6));
+        assert_ne!(noisy, plain);
+        assert!((noisy.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+    }
+}
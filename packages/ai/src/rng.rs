@@ -0,0 +1,123 @@
+//! Reproducible randomness.
+//!
+//! Everything random in this crate draws from a generator or seed the caller hands it,
+//! so playouts, searches and whole analysis runs can be repeated exactly. [`GameRng`]
+//! is the generator used whenever the crate makes one itself. ChaCha8 is fast, and unlike
+//! [`StdRng`](rand::rngs::StdRng) its output is guaranteed to stay the same across versions.
+//!
+//! Parallel tasks shouldn't share a generator, because then the order in which the threads
+//! happen to draw from it changes the results. Every task gets its own [`task_rng`] instead:
+//! ChaCha has 2<sup>64</sup> independent streams per seed, and task `n` uses stream `n`
+//! of the master seed. The results then only depend on the master seed, not on which
+//! thread ran which task.
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The random number generator used throughout the crate
+pub type GameRng = ChaCha8Rng;
+
+/// A generator that always produces the same numbers for the same `seed`
+pub fn seeded(seed: u64) -> GameRng {
+    GameRng::seed_from_u64(seed)
+}
+/// The generator for task number `task` of a parallel run seeded with `master_seed`
+pub fn task_rng(master_seed: u64, task: u64) -> GameRng {
+    let mut rng = seeded(master_seed);
+    rng.set_stream(task);
+    rng
+}
+/// A generator seeded by the operating system, for when nothing needs to be reproduced
+pub fn from_entropy() -> GameRng {
+    GameRng::from_entropy()
+}
+/// A fresh master seed from the operating system. Print it so the run can be repeated
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+/// A sample from the symmetric [Dirichlet distribution](https://en.wikipedia.org/wiki/Dirichlet_distribution)
+/// over `n` outcomes with concentration `alpha`: `n` random numbers that add up to `1`. The smaller
+/// `alpha` is, the more the total piles up on a few of them
+pub fn dirichlet(alpha: f64, n: usize, rng: &mut impl Rng) -> Vec<f64> {
+    let mut sample: Vec<f64> = (0..n).map(|_| gamma(alpha, rng)).collect();
+    let total: f64 = sample.iter().sum();
+    for x in &mut sample {
+        *x = if total > 0.0 {
+            *x / total
+        } else {
+            1.0 / n as f64
+        };
+    }
+    sample
+}
+/// A sample from the gamma distribution with shape `alpha` and scale `1`, by
+/// [Marsaglia and Tsang's method](https://doi.org/10.1145/358407.358414)
+fn gamma(alpha: f64, rng: &mut impl Rng) -> f64 {
+    if alpha < 1.0 {
+        let u: f64 = rng.gen();
+        return gamma(alpha + 1.0, rng) * u.powf(1.0 / alpha);
+    }
+    let d = alpha - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        // A standard normal sample, by the Box-Muller transform
+        let (u1, u2): (f64, f64) = rng.gen();
+        let x = (-2.0 * (1.0 - u1).ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u: f64 = rng.gen();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::prelude::*;
+
+    use super::*;
+    use crate::simulate_game;
+    use super_ttt::Game;
+
+    #[test]
+    fn tasks_dont_depend_on_threads() {
+        let run = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| {
+                    (0..40)
+                        .into_par_iter()
+                        .map(|task| simulate_game(Game::new(), &mut task_rng(7, task)).1)
+                        .collect::<Vec<i8>>()
+                })
+        };
+        assert_eq!(run(1), run(4));
+        assert_ne!(
+            task_rng(7, 0).gen::<u64>(),
+            task_rng(7, 1).gen::<u64>(),
+            "tasks share a stream"
+        );
+    }
+
+    #[test]
+    fn dirichlet_samples_add_up() {
+        let mut rng = seeded(0);
+        let mut means = [0.0; 4];
+        for _ in 0..2_000 {
+            let sample = dirichlet(0.3, 4, &mut rng);
+            assert!((sample.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            assert!(sample.iter().all(|&x| x >= 0.0));
+            for (mean, x) in means.iter_mut().zip(sample) {
+                *mean += x / 2_000.0;
+            }
+        }
+        assert!(
+            means.iter().all(|mean| (mean - 0.25).abs() < 0.03),
+            "{means:?}"
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Self-play: an engine playing itself to make training data for a [network](crate::nn).
+//!
+//! [`self_play`] plays one game with a [PUCT search](crate::puct) for both sides, and records
+//! every position with how many visits each move got and how the game ended. For the first few
+//! moves it picks moves at random by their visits (with a [temperature](SelfPlayConfig::temperature)),
+//! and [Dirichlet noise](crate::puct::DirichletNoise) at the root makes the search try moves the
+//! priors don't like, so that no two games are the same. After that it plays the most visited move.
+//!
+//! # Dataset format
+//!
+//! A dataset is the 8 byte [`FILE_HEADER`], the seed of the run as 8 bytes, then every game
+//! back to back. All numbers are little-endian. A game is the number of positions in it as
+//! 2 bytes, followed by the positions:
+//!
+//! - 21 bytes: the position, in the [binary format](super_ttt::binary) of [`Game::to_bytes`]
+//! - 1 byte: the outcome for the player to move, as a signed byte: `1` for a win, `0` for a tie, `-1` for a loss
+//! - 1 byte: the number of moves with visits
+//! - 3 bytes per move: its [square coordinate](super_ttt::to_square_coords) `row * 9 + col`, then its
+//!   visits as 2 bytes. If a move had more than 65,535 visits, all of them are scaled down to fit
+//!
+//! Game `n` of a run is played with [task stream](crate::rng::task_rng) `n` of its seed, so an
+//! interrupted run can pick up where it stopped and still write exactly the same games.
+use std::io::{self, Read, Write};
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use super_ttt::binary::read_record;
+use super_ttt::{from_square_coords, Game, GameState, Move, GRID_SIZE};
+
+use crate::limits::SearchLimits;
+use crate::nn::{Example, SQUARES};
+use crate::puct::{DirichletNoise, PolicyValue, Puct, PuctConfig};
+use crate::{apply_move, square_index};
+
+/// The first bytes of every dataset. The last byte is the format version
+pub const FILE_HEADER: [u8; 8] = *b"STTTSPD\x01";
+
+/// How [`self_play`] plays
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfPlayConfig {
+    /// How every move is searched. Its seed is ignored, every game draws its own.
+    /// Defaults to 400 iterations with [Dirichlet noise](DirichletNoise::default)
+    pub search: PuctConfig,
+    /// For the first [`temperature_moves`](Self::temperature_moves) moves, every move is picked
+    /// with a probability proportional to `visits^(1 / temperature)`. `1` follows the visits,
+    /// higher values play more randomly and `0` always plays the most visited move
+    pub temperature: f64,
+    /// How many moves of every game are played with the temperature
+    pub temperature_moves: usize,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        SelfPlayConfig {
+            search: PuctConfig {
+                limits: SearchLimits::iterations(400),
+                noise: Some(DirichletNoise::default()),
+                ..Default::default()
+            },
+            temperature: 1.0,
+            temperature_moves: 12,
+        }
+    }
+}
+
+/// A position from a self-play game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The position
+    pub game: Game,
+    /// How many visits the search gave every move it tried
+    pub visits: Vec<(Move, u32)>,
+    /// How the game ended for the player to move: `1` for a win, `0` for a tie, `-1` for a loss
+    pub outcome: i8,
+}
+
+impl Record {
+    /// What a network should learn from this position: the share of the visits every move
+    /// got as its policy, and the outcome as its value
+    pub fn example(&self) -> Example {
+        let total: u32 = self.visits.iter().map(|&(_, visits)| visits).sum();
+        let mut policy = [0.0; SQUARES];
+        for &(move_, visits) in &self.visits {
+            policy[square_index(move_)] = visits as f32 / total.max(1) as f32;
+        }
+        Example {
+            game: self.game,
+            policy,
+            value: self.outcome as f32,
+        }
+    }
+}
+
+/// Play a game from `game` with `evaluator` guiding both sides, and record every position.
+/// The same `rng` always plays the same game
+pub fn self_play<E: PolicyValue>(
+    evaluator: E,
+    config: &SelfPlayConfig,
+    mut game: Game,
+    rng: &mut impl Rng,
+) -> Vec<Record> {
+    let mut puct = Puct::new(
+        PuctConfig {
+            seed: Some(rng.gen()),
+            ..config.search
+        },
+        evaluator,
+    );
+    let mut records = Vec::new();
+    while game.get_winner() == GameState::InProgress {
+        let best = puct.search(game);
+        let visits: Vec<(Move, u32)> = puct
+            .root_stats()
+            .iter()
+            .filter(|stats| stats.visits > 0)
+            .map(|stats| (stats.move_, stats.visits))
+            .collect();
+        let move_ = if records.len() < config.temperature_moves && config.temperature > 0.0 {
+            // Relative to the most visited move, so that a low temperature can't overflow
+            let exponent = 1.0 / config.temperature;
+            let most = visits.iter().map(|&(_, visits)| visits).max().unwrap_or(1) as f64;
+            visits
+                .choose_weighted(rng, |&(_, visits)| (visits as f64 / most).powf(exponent))
+                .map_or(best, |&(move_, _)| move_)
+        } else {
+            best
+        };
+        records.push(Record {
+            game,
+            visits,
+            outcome: 0,
+        });
+        game = apply_move(move_, game).expect("the search found an invalid move");
+    }
+    for record in &mut records {
+        record.outcome = match game.get_winner() {
+            GameState::Winner(winner) if winner == record.game.current_player => 1,
+            GameState::Winner(_) => -1,
+            _ => 0,
+        };
+    }
+    records
+}
+
+/// Writes self-play games in the [dataset format](self#dataset-format)
+pub struct DatasetWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> DatasetWriter<W> {
+    /// Start a new dataset for a run with `seed` by writing the header
+    pub fn new(mut writer: W, seed: u64) -> io::Result<Self> {
+        writer.write_all(&FILE_HEADER)?;
+        writer.write_all(&seed.to_le_bytes())?;
+        Ok(DatasetWriter { writer })
+    }
+    /// Continue a dataset that already has a header, like one opened in append mode
+    pub fn append(writer: W) -> Self {
+        DatasetWriter { writer }
+    }
+    /// Write the positions of a game
+    pub fn write_game(&mut self, records: &[Record]) -> io::Result<()> {
+        let count = u16::try_from(records.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "the game is too long"))?;
+        self.writer.write_all(&count.to_le_bytes())?;
+        for record in records {
+            self.writer.write_all(&record.game.to_bytes())?;
+            self.writer.write_all(&record.outcome.to_le_bytes())?;
+            self.writer.write_all(&[record.visits.len() as u8])?;
+            let most = record.visits.iter().map(|&(_, visits)| visits).max();
+            let scale = most.map_or(1.0, |most| (u16::MAX as f64 / most as f64).min(1.0));
+            for &(move_, visits) in &record.visits {
+                self.writer.write_all(&[square_index(move_) as u8])?;
+                let visits = (visits as f64 * scale) as u16;
+                self.writer.write_all(&visits.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+    /// Get back the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads datasets written by a [`DatasetWriter`], a game at a time.
+/// Also an [`Iterator`] over the games
+pub struct DatasetReader<R: Read> {
+    reader: R,
+    seed: u64,
+    /// The length of everything read up to the end of the last whole game
+    complete: u64,
+}
+
+impl<R: Read> DatasetReader<R> {
+    /// Start reading a dataset, checking its header
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; FILE_HEADER.len() + 8];
+        reader.read_exact(&mut header)?;
+        if header[..FILE_HEADER.len()] != FILE_HEADER {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a super tic tac toe self-play dataset",
+            ));
+        }
+        Ok(DatasetReader {
+            reader,
+            seed: u64::from_le_bytes(header[FILE_HEADER.len()..].try_into().unwrap()),
+            complete: header.len() as u64,
+        })
+    }
+    /// The seed of the run that wrote the dataset
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// How many bytes of the file hold whole games (and the header). A file that was cut off
+    /// partway through a game can be truncated to this length and appended to
+    pub fn complete_len(&self) -> u64 {
+        self.complete
+    }
+    /// Read the positions of the next game, or `None` at the end of the file. A file that ends
+    /// partway through a game is an [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) error, and
+    /// anything else that doesn't follow the format is an [`InvalidData`](io::ErrorKind::InvalidData) error
+    pub fn read_game(&mut self) -> io::Result<Option<Vec<Record>>> {
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut count = [0; 2];
+        if !read_record(&mut self.reader, &mut count)? {
+            return Ok(None);
+        }
+        let mut length = count.len() as u64;
+        let mut records = Vec::new();
+        for _ in 0..u16::from_le_bytes(count) {
+            let mut position = [0; super_ttt::binary::ENCODED_LEN + 2];
+            self.reader.read_exact(&mut position)?;
+            let (game, rest) = position.split_at(super_ttt::binary::ENCODED_LEN);
+            let game = Game::from_bytes(game).map_err(|err| invalid(err.to_string()))?;
+            let outcome = rest[0] as i8;
+            if !(-1..=1).contains(&outcome) {
+                return Err(invalid("an outcome isn't a win, tie or loss".to_string()));
+            }
+            let mut visits = vec![0; 3 * rest[1] as usize];
+            self.reader.read_exact(&mut visits)?;
+            let visits = visits
+                .chunks_exact(3)
+                .map(|chunk| {
+                    let square = chunk[0] as usize;
+                    if square >= GRID_SIZE * GRID_SIZE {
+                        return Err(invalid("a move is off the board".to_string()));
+                    }
+                    let move_ = from_square_coords(square / GRID_SIZE, square % GRID_SIZE);
+                    Ok((move_, u16::from_le_bytes([chunk[1], chunk[2]]) as u32))
+                })
+                .collect::<io::Result<_>>()?;
+            length += (position.len() + 3 * rest[1] as usize) as u64;
+            records.push(Record {
+                game,
+                visits,
+                outcome,
+            });
+        }
+        self.complete += length;
+        Ok(Some(records))
+    }
+}
+
+impl<R: Read> Iterator for DatasetReader<R> {
+    type Item = io::Result<Vec<Record>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_game().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nn::Network;
+    use crate::puct::Rollouts;
+    use crate::rng::{seeded, task_rng};
+
+    fn config() -> SelfPlayConfig {
+        SelfPlayConfig {
+            search: PuctConfig {
+                limits: SearchLimits::iterations(20),
+                ..SelfPlayConfig::default().search
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn records_every_position() {
+        let records = self_play(Rollouts, &config(), Game::new(), &mut seeded(0));
+        // The player to move in the last position ended the game, so they can't have lost
+        assert!(records.last().unwrap().outcome >= 0);
+        for pair in records.windows(2) {
+            assert_eq!(pair[0].outcome, -pair[1].outcome);
+            assert_ne!(pair[0].game.current_player, pair[1].game.current_player);
+        }
+        for record in &records {
+            assert_eq!(
+                record.visits.iter().map(|&(_, visits)| visits).sum::<u32>(),
+                19
+            );
+            let example = record.example();
+            assert!((example.policy.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+        }
+        assert_eq!(
+            records,
+            self_play(Rollouts, &config(), Game::new(), &mut seeded(0))
+        );
+    }
+
+    #[test]
+    fn plays_at_a_low_temperature() {
+        let config = SelfPlayConfig {
+            temperature: 0.01,
+            ..config()
+        };
+        let records = self_play(Rollouts, &config, Game::new(), &mut seeded(1));
+        assert!(!records.is_empty());
+    }
+
+    #[test]
+    fn round_trip() {
+        let network = Network::new(&[8], &mut seeded(0));
+        let games: Vec<Vec<Record>> = (0..2)
+            .map(|game| self_play(&network, &config(), Game::new(), &mut task_rng(3, game)))
+            .collect();
+        let mut writer = DatasetWriter::new(Vec::new(), 3).unwrap();
+        for game in &games {
+            writer.write_game(game).unwrap();
+        }
+        let bytes = writer.into_inner();
+        let reader = DatasetReader::new(&bytes[..]).unwrap();
+        assert_eq!(reader.seed(), 3);
+        let read: Vec<_> = reader.collect::<io::Result<_>>().unwrap();
+        assert_eq!(read, games);
+
+        // A dataset cut off in the second game still has the first one
+        let mut reader = DatasetReader::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(reader.read_game().unwrap().as_ref(), Some(&games[0]));
+        let complete = reader.complete_len();
+        let err = reader.read_game().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let mut first = DatasetWriter::new(Vec::new(), 3).unwrap();
+        first.write_game(&games[0]).unwrap();
+        assert_eq!(complete, first.into_inner().len() as u64);
+
+        // The outcome of the first position of the first game
+        let mut corrupt = bytes.clone();
+        corrupt[FILE_HEADER.len() + 8 + 2 + super_ttt::binary::ENCODED_LEN] = 2;
+        let mut reader = DatasetReader::new(&corrupt[..]).unwrap();
+        let err = reader.read_game().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
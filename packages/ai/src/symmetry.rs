@@ -0,0 +1,141 @@
+//! The 8 symmetries of the board: rotations and reflections.
+//!
+//! Rotating or reflecting the whole 9x9 grid turns every small board the same way it turns the
+//! big board, and sends every move to the matching board, so a transformed position plays exactly
+//! like the original. [`canonical`] picks one position out of every group of equivalent ones,
+//! which lets an [opening book](crate::book) store them once.
+use super_ttt::{from_square_coords, to_square_coords, Game, Move, BOARD_SIZE, GRID_SIZE};
+
+/// A rotation or reflection of the grid. It transposes the grid first (if it does),
+/// then flips the rows, then the columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+    transpose: bool,
+    flip_rows: bool,
+    flip_cols: bool,
+}
+
+impl Symmetry {
+    /// Leaves everything where it is
+    pub const IDENTITY: Symmetry = Symmetry::new(false, false, false);
+    /// Every symmetry, starting with [`Symmetry::IDENTITY`]
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::IDENTITY,
+        Symmetry::new(false, false, true),
+        Symmetry::new(false, true, false),
+        Symmetry::new(false, true, true),
+        Symmetry::new(true, false, false),
+        Symmetry::new(true, false, true),
+        Symmetry::new(true, true, false),
+        Symmetry::new(true, true, true),
+    ];
+
+    const fn new(transpose: bool, flip_rows: bool, flip_cols: bool) -> Self {
+        Symmetry {
+            transpose,
+            flip_rows,
+            flip_cols,
+        }
+    }
+    /// The symmetry that undoes this one
+    pub fn inverse(self) -> Self {
+        if self.transpose {
+            // Flipping rows after a transpose is flipping columns before it
+            Symmetry::new(true, self.flip_cols, self.flip_rows)
+        } else {
+            self
+        }
+    }
+    /// Where the square at `(row, col)` of a `size` by `size` grid ends up
+    fn coords(self, (row, col): (usize, usize), size: usize) -> (usize, usize) {
+        let (row, col) = if self.transpose {
+            (col, row)
+        } else {
+            (row, col)
+        };
+        (
+            if self.flip_rows { size - 1 - row } else { row },
+            if self.flip_cols { size - 1 - col } else { col },
+        )
+    }
+    /// Where the square at [square coordinates](super_ttt::to_square_coords) `(row, col)` ends up
+    pub fn square(self, square: (usize, usize)) -> (usize, usize) {
+        self.coords(square, GRID_SIZE)
+    }
+    /// Where `move_` ends up
+    pub fn move_(self, (board_row, board_col, cell_row, cell_col): Move) -> Move {
+        let (row, col) = self.square(to_square_coords(board_row, board_col, cell_row, cell_col));
+        from_square_coords(row, col)
+    }
+    /// The position with every square moved
+    pub fn game(self, game: &Game) -> Game {
+        let mut transformed = *game;
+        for (square, value) in game.iter_squares() {
+            transformed[self.square(square)] = value;
+        }
+        transformed.last_move_cords = game
+            .last_move_cords
+            .map(|board| self.coords(board, BOARD_SIZE));
+        transformed
+    }
+}
+
+/// The representative of every position equivalent to `game`, along with a symmetry that turns
+/// `game` into it. Equivalent positions always get the same representative
+pub fn canonical(game: &Game) -> (Game, Symmetry) {
+    Symmetry::ALL
+        .into_iter()
+        .map(|symmetry| (symmetry.game(game), symmetry))
+        .min_by_key(|(game, _)| game.to_bytes())
+        .expect("there are symmetries")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply_move;
+
+    fn sample_game() -> Game {
+        let mut game = Game::new();
+        for move_ in [(0, 1, 2, 0), (2, 0, 1, 1), (1, 1, 0, 2), (0, 2, 2, 2)] {
+            game = apply_move(move_, game).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn symmetries_preserve_the_game() {
+        let game = sample_game();
+        for symmetry in Symmetry::ALL {
+            let transformed = symmetry.game(&game);
+            assert_eq!(symmetry.inverse().game(&transformed), game);
+            assert_eq!(transformed.get_winner(), game.get_winner());
+            // Legal moves map onto legal moves
+            let mut expected: Vec<_> = game
+                .legal_moves()
+                .iter()
+                .map(|&move_| symmetry.move_(move_))
+                .collect();
+            let mut moves = transformed.legal_moves().to_vec();
+            expected.sort();
+            moves.sort();
+            assert_eq!(moves, expected);
+            for &move_ in game.legal_moves().iter() {
+                assert_eq!(
+                    apply_move(symmetry.move_(move_), transformed).unwrap(),
+                    symmetry.game(&apply_move(move_, game).unwrap())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn equivalent_positions_share_a_representative() {
+        let game = sample_game();
+        let (representative, symmetry) = canonical(&game);
+        assert_eq!(symmetry.game(&game), representative);
+        for other in Symmetry::ALL {
+            assert_eq!(canonical(&other.game(&game)).0, representative);
+        }
+    }
+}
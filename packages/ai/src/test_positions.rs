@@ -0,0 +1,28 @@
+//! Positions the tests of the searches share
+use super_ttt::{Game, GameState};
+
+use crate::apply_move;
+
+/// X has won the top left and top middle boards and is sent to the top right one,
+/// so `(0, 2, 0, 2)` wins right away
+pub fn immediate_win() -> Game {
+    Game::from_position_string(
+        "XXXXXXXX./........./........./OO.OO.OO./........./........./OO......./........./......... X 2",
+    )
+    .unwrap()
+}
+
+/// The last `count` positions before the end of a game where every move is picked by
+/// a simple formula, the last one first
+pub fn late_positions(count: usize) -> Vec<Game> {
+    let mut positions = Vec::new();
+    let mut game = Game::new();
+    let mut i = 0;
+    while game.get_winner() == GameState::InProgress {
+        positions.push(game);
+        let moves = game.legal_moves();
+        game = apply_move(moves[(i * 5 + 2) % moves.len()], game).unwrap();
+        i += 1;
+    }
+    positions.into_iter().rev().take(count).collect()
+}
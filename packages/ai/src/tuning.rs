@@ -0,0 +1,231 @@
+//! Fitting evaluation [`Weights`] to the results of real games,
+//! [Texel](https://www.chessprogramming.org/Texel%27s_Tuning_Method)-style.
+//!
+//! Every position in the corpus is labelled with how its game ended. The evaluation is turned
+//! into a predicted score with a logistic curve, `1 / (1 + e^(-k * eval))`, and the weights are
+//! fitted by gradient descent to minimize the mean squared difference between the predicted and
+//! actual scores (`1` for a win of the player to move, `0.5` for a tie, `0` for a loss).
+//! Since the evaluation is a weighted sum of [features](crate::eval::features), this is
+//! logistic regression. The scale `k` is fitted first, to the starting weights, and then kept fixed.
+//!
+//! A corpus file is plain text with one position per line: a [position string](super_ttt::notation)
+//! followed by a space and the result of its game (`1-0`, `0-1` or `½-½`).
+use std::io::{self, BufRead, Write};
+
+use super_ttt::{Game, GameState};
+
+use crate::agent::{play_game_with, Agent};
+use crate::eval::{features, Weights, FEATURE_COUNT};
+
+/// A position and the result of the game it's from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// The position
+    pub game: Game,
+    /// How the game ended
+    pub result: GameState,
+}
+
+/// Write samples to a corpus file
+pub fn write_corpus(mut writer: impl Write, samples: &[Sample]) -> io::Result<()> {
+    for sample in samples {
+        writeln!(
+            writer,
+            "{} {}",
+            sample.game.to_position_string(),
+            sample.result
+        )?;
+    }
+    writer.flush()
+}
+
+/// Read every sample of a corpus file. Lines that aren't samples, including ones whose
+/// game hasn't ended (`*`), are [`InvalidData`](io::ErrorKind::InvalidData) errors
+pub fn read_corpus(reader: impl BufRead) -> io::Result<Vec<Sample>> {
+    let mut samples = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {} isn't a position followed by a result", i + 1),
+            )
+        };
+        let (position, result) = line.trim().rsplit_once(' ').ok_or_else(invalid)?;
+        let result = result.parse().map_err(|_| invalid())?;
+        if result == GameState::InProgress {
+            return Err(invalid());
+        }
+        samples.push(Sample {
+            game: Game::from_position_string(position).map_err(|_| invalid())?,
+            result,
+        });
+    }
+    Ok(samples)
+}
+
+/// Play `x` against `o` from `game` and label every position along the way with the result
+pub fn record_game(
+    x: &mut (impl Agent + ?Sized),
+    o: &mut (impl Agent + ?Sized),
+    game: Game,
+) -> Vec<Sample> {
+    let mut positions = Vec::new();
+    let result = play_game_with(x, o, game, |game| positions.push(*game));
+    positions
+        .into_iter()
+        .map(|game| Sample { game, result })
+        .collect()
+}
+
+/// Fits [`Weights`] to a corpus. See the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct Tuner {
+    /// The features of every position in progress and its actual score for the player to move
+    samples: Vec<([f64; FEATURE_COUNT], f64)>,
+    /// `k` of the logistic curve
+    pub scale: f64,
+    /// How far each step of gradient descent may move a weight, roughly
+    pub learning_rate: f64,
+}
+
+impl Tuner {
+    /// Prepare to tune on `samples`. Finished games are skipped, since they're never evaluated,
+    /// and so are samples from games that didn't finish, since they have nothing to fit to
+    pub fn new(samples: &[Sample]) -> Self {
+        let samples = samples
+            .iter()
+            .filter(|sample| sample.game.get_winner() == GameState::InProgress)
+            .filter_map(|sample| {
+                let target = match sample.result {
+                    GameState::Winner(player) if player == sample.game.current_player => 1.0,
+                    GameState::Winner(_) => 0.0,
+                    GameState::Tie => 0.5,
+                    GameState::InProgress => return None,
+                };
+                Some((features(&sample.game), target))
+            })
+            .collect();
+        Tuner {
+            samples,
+            scale: 0.01,
+            learning_rate: 1.0,
+        }
+    }
+    /// The number of positions being tuned on
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    /// Whether there's nothing to tune on
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+    fn predict(&self, weights: &Weights, features: &[f64; FEATURE_COUNT]) -> f64 {
+        let eval: f64 = features.iter().zip(weights.0).map(|(f, w)| f * w).sum();
+        1.0 / (1.0 + (-self.scale * eval).exp())
+    }
+    /// The mean squared error of `weights`' predictions
+    pub fn error(&self, weights: &Weights) -> f64 {
+        self.samples
+            .iter()
+            .map(|(features, target)| (self.predict(weights, features) - target).powi(2))
+            .sum::<f64>()
+            / self.samples.len().max(1) as f64
+    }
+    /// Set [`Tuner::scale`] to the one that fits `weights` best and return it
+    pub fn fit_scale(&mut self, weights: &Weights) -> f64 {
+        // The error is unimodal in log(k), so a ternary search finds the minimum
+        let (mut low, mut high) = (1e-5_f64.ln(), 1.0_f64.ln());
+        for _ in 0..100 {
+            let third = (high - low) / 3.0;
+            self.scale = (low + third).exp();
+            let low_error = self.error(weights);
+            self.scale = (high - third).exp();
+            if low_error < self.error(weights) {
+                high -= third;
+            } else {
+                low += third;
+            }
+        }
+        self.scale = ((low + high) / 2.0).exp();
+        self.scale
+    }
+    /// Run `epochs` steps of gradient descent ([Adam](https://arxiv.org/abs/1412.6980)) over the
+    /// whole corpus, starting from `weights`. `on_epoch` gets each epoch's number and error
+    pub fn fit(
+        &self,
+        mut weights: Weights,
+        epochs: usize,
+        mut on_epoch: impl FnMut(usize, f64),
+    ) -> Weights {
+        const BETA1: f64 = 0.9;
+        const BETA2: f64 = 0.999;
+        let mut momentum = [0.0; FEATURE_COUNT];
+        let mut velocity = [0.0; FEATURE_COUNT];
+        for epoch in 1..=epochs {
+            let mut gradient = [0.0; FEATURE_COUNT];
+            for (features, target) in &self.samples {
+                let predicted = self.predict(&weights, features);
+                let slope = 2.0 * (predicted - target) * predicted * (1.0 - predicted) * self.scale;
+                for (g, feature) in gradient.iter_mut().zip(features) {
+                    *g += slope * feature;
+                }
+            }
+            for i in 0..FEATURE_COUNT {
+                let g = gradient[i] / self.samples.len().max(1) as f64;
+                momentum[i] = BETA1 * momentum[i] + (1.0 - BETA1) * g;
+                velocity[i] = BETA2 * velocity[i] + (1.0 - BETA2) * g * g;
+                let m = momentum[i] / (1.0 - BETA1.powi(epoch as i32));
+                let v = velocity[i] / (1.0 - BETA2.powi(epoch as i32));
+                weights.0[i] -= self.learning_rate * m / (v.sqrt() + 1e-12);
+            }
+            on_epoch(epoch, self.error(&weights));
+        }
+        weights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::RandomAgent;
+
+    fn corpus() -> Vec<Sample> {
+        (0..100)
+            .flat_map(|seed| {
+                let (mut x, mut o) = (RandomAgent::new(seed), RandomAgent::new(seed + 1_000));
+                record_game(&mut x, &mut o, Game::new())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn corpus_round_trip() {
+        let (mut x, mut o) = (RandomAgent::new(1), RandomAgent::new(2));
+        let samples = record_game(&mut x, &mut o, Game::new());
+        assert!(samples.iter().all(|s| s.result == samples[0].result));
+        let mut file = Vec::new();
+        write_corpus(&mut file, &samples).unwrap();
+        assert_eq!(read_corpus(file.as_slice()).unwrap(), samples);
+        assert!(read_corpus("not a position 1-0".as_bytes()).is_err());
+        let unfinished = format!("{} *", Game::new().to_position_string());
+        let err = read_corpus(unfinished.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn fitting_lowers_the_error() {
+        let mut tuner = Tuner::new(&corpus());
+        let start = Weights::default();
+        tuner.fit_scale(&start);
+        let start_error = tuner.error(&start);
+        let mut errors = Vec::new();
+        let fitted = tuner.fit(start, 50, |_, error| errors.push(error));
+        assert_eq!(errors.len(), 50);
+        assert_eq!(*errors.last().unwrap(), tuner.error(&fitted));
+        assert!(tuner.error(&fitted) < start_error);
+    }
+}
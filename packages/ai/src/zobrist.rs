@@ -0,0 +1,84 @@
+//! [Zobrist hashing](https://www.chessprogramming.org/Zobrist_Hashing) of positions,
+//! for the transposition tables of the searches.
+use super_ttt::{Game, GameState, Move, Player, GRID_SIZE};
+
+use crate::{apply_move, player_index, square_index};
+
+const SQUARES: usize = GRID_SIZE * GRID_SIZE;
+
+/// The random numbers every part of a hash comes from
+struct Keys {
+    /// By player, then square coordinate `row * 9 + col`
+    squares: [[u64; SQUARES]; 2],
+    o_to_move: u64,
+    /// By the board the player to move is sent to, with `9` for a free move
+    forced_board: [u64; 10],
+}
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const KEYS: Keys = {
+    let mut keys = Keys {
+        squares: [[0; SQUARES]; 2],
+        o_to_move: 0,
+        forced_board: [0; 10],
+    };
+    let mut state = 0x5eed;
+    let mut i = 0;
+    while i < 2 * SQUARES {
+        let (next, key) = splitmix64(state);
+        keys.squares[i / SQUARES][i % SQUARES] = key;
+        state = next;
+        i += 1;
+    }
+    let (next, key) = splitmix64(state);
+    keys.o_to_move = key;
+    state = next;
+    let mut i = 0;
+    while i < 10 {
+        let (next, key) = splitmix64(state);
+        keys.forced_board[i] = key;
+        state = next;
+        i += 1;
+    }
+    keys
+};
+
+/// The board the player to move has to play in, or `9` if they can play anywhere
+fn forced_board(game: &Game) -> usize {
+    match game.last_move_cords {
+        Some((row, col)) if game.boards[row][col].get_winner() == GameState::InProgress => {
+            row * 3 + col
+        }
+        _ => 9,
+    }
+}
+/// The hash of a position
+pub(crate) fn hash(game: &Game) -> u64 {
+    let mut hash = KEYS.forced_board[forced_board(game)];
+    if game.current_player == Player::O {
+        hash ^= KEYS.o_to_move;
+    }
+    for ((row, col), square) in game.iter_squares() {
+        if let super_ttt::Square::Occupied(player) = square {
+            hash ^= KEYS.squares[player_index(player)][row * GRID_SIZE + col];
+        }
+    }
+    hash
+}
+/// Play `move_`, updating the hash of the position along the way
+pub(crate) fn play(game: &Game, hash: u64, move_: Move) -> (Game, u64) {
+    let child = apply_move(move_, *game).expect("invalid move generated");
+    let hash = hash
+        ^ KEYS.squares[player_index(game.current_player)][square_index(move_)]
+        ^ KEYS.o_to_move
+        ^ KEYS.forced_board[forced_board(game)]
+        ^ KEYS.forced_board[forced_board(&child)];
+    (child, hash)
+}
@@ -1,32 +1,47 @@
-use ai::simulate_game;
+//! Play lots of random games in parallel and print how often each side wins.
+//!
+//! `analysis [games] [seed] [policy]`: game `n` draws its moves from stream `n` of the seed, so the
+//! same seed gives exactly the same results no matter how many threads run the games.
+//! The games are played by a [rollout policy](ai::policy): `uniform` (the default), `win`,
+//! `win-or-block`, `avoid-gifts` or `greedy`.
+use ai::playout::rollout;
+use ai::policy::{AvoidGifts, EpsilonGreedy, RolloutPolicy, Uniform, WinIfPossible, WinOrBlock};
+use ai::rng::{random_seed, task_rng};
 use rayon::prelude::*;
 
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
-};
 use super_ttt::Game;
 const REPETITION_TIMES: usize = 10_000;
 fn main() {
-    let results = Arc::new(Mutex::new(HashMap::from([(0, 0), (-1, 0), (1, 0)])));
-    (0..REPETITION_TIMES).into_par_iter().for_each(|_| {
-        results
-            .clone()
-            .lock()
-            .unwrap()
-            .entry(simulate_game(Game::new()).1)
-            .and_modify(|x| *x += 1);
-    });
-    {
-        let results = results.lock().unwrap();
-        println!(
-            "First player wins: {}",
-            results[&1] as f32 / REPETITION_TIMES as f32
-        );
-        println!(
-            "Second player wins: {}",
-            results[&-1] as f32 / REPETITION_TIMES as f32
-        );
-        println!("Ties: {}", results[&0] as f32 / REPETITION_TIMES as f32);
-    }
+    let mut args = std::env::args().skip(1);
+    let games: usize = args
+        .next()
+        .map_or(REPETITION_TIMES, |arg| arg.parse().expect("games"));
+    let seed: u64 = args
+        .next()
+        .map_or_else(random_seed, |arg| arg.parse().expect("seed"));
+    let policy: Box<dyn RolloutPolicy + Send + Sync> = match args.next().as_deref() {
+        None | Some("uniform") => Box::new(Uniform),
+        Some("win") => Box::new(WinIfPossible),
+        Some("win-or-block") => Box::new(WinOrBlock),
+        Some("avoid-gifts") => Box::new(AvoidGifts),
+        Some("greedy") => Box::new(EpsilonGreedy::new(0.1)),
+        Some(name) => panic!("there is no policy called {name:?}"),
+    };
+    println!("Seed: {seed}");
+
+    // Wins for O, ties and wins for X
+    let results = (0..games)
+        .into_par_iter()
+        .map(|game| rollout(Game::new(), &policy, &mut task_rng(seed, game as u64)).sign())
+        .fold(
+            || [0; 3],
+            |mut counts, result| {
+                counts[(result + 1) as usize] += 1;
+                counts
+            },
+        )
+        .reduce(|| [0; 3], |a, b| [a[0] + b[0], a[1] + b[1], a[2] + b[2]]);
+    println!("First player wins: {}", results[2] as f32 / games as f32);
+    println!("Second player wins: {}", results[0] as f32 / games as f32);
+    println!("Ties: {}", results[1] as f32 / games as f32);
 }
@@ -0,0 +1,16 @@
+use std::env;
+
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=STTT_UPDATE_HEADER");
+    let bindings = cbindgen::generate(&crate_dir).expect("Unable to generate C bindings");
+    // The build only writes to OUT_DIR; the header in the source tree is updated on request
+    // and checked against this one by the `header_is_up_to_date` test
+    bindings.write_to_file(format!("{out_dir}/super_ttt.h"));
+    if env::var_os("STTT_UPDATE_HEADER").is_some() {
+        bindings.write_to_file(format!("{crate_dir}/include/super_ttt.h"));
+    }
+}
@@ -0,0 +1,195 @@
+#ifndef SUPER_TTT_H
+#define SUPER_TTT_H
+
+/* This file is generated by cbindgen from packages/capi/src/lib.rs. Do not edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The most legal moves a position can have
+ */
+#define STTT_MAX_MOVES 81
+
+/**
+ * The size of the buffer needed by [`sttt_game_to_position`], including the trailing `NUL`
+ */
+#define STTT_POSITION_BUFFER_SIZE 94
+
+/**
+ * The result of [`sttt_game_make_move`]. Every error matches an
+ * [`InvalidMoveError`] variant of the same name
+ */
+typedef enum StttMoveResult {
+  /**
+   * The move was made
+   */
+  STTT_MOVE_RESULT_OK = 0,
+  /**
+   * The specified cell is already occupied
+   */
+  STTT_MOVE_RESULT_CELL_ALREADY_OCCUPIED = 1,
+  /**
+   * The specified board does not match the coordinates of the opponent's last move
+   */
+  STTT_MOVE_RESULT_INVALID_BOARD = 2,
+  /**
+   * One of the coordinates is not between 0 and 2
+   */
+  STTT_MOVE_RESULT_OUT_OF_BOUNDS = 3,
+} StttMoveResult;
+
+/**
+ * A player
+ */
+typedef enum StttPlayer {
+  /**
+   * `X`, who always moves first
+   */
+  STTT_PLAYER_X = 0,
+  /**
+   * `O`
+   */
+  STTT_PLAYER_O = 1,
+} StttPlayer;
+
+/**
+ * The state of a game, as returned by [`sttt_game_get_winner`]
+ */
+typedef enum StttState {
+  /**
+   * Nobody has won yet and there are moves left
+   */
+  STTT_STATE_IN_PROGRESS = 0,
+  /**
+   * `X` won
+   */
+  STTT_STATE_X_WON = 1,
+  /**
+   * `O` won
+   */
+  STTT_STATE_O_WON = 2,
+  /**
+   * Every board is finished and nobody won
+   */
+  STTT_STATE_TIE = 3,
+} StttState;
+
+/**
+ * An opaque handle to a game
+ */
+typedef struct StttGame StttGame;
+
+/**
+ * A move, in the same order as the arguments of [`sttt_game_make_move`]
+ */
+typedef struct StttMove {
+  /**
+   * Row of the board, 0 to 2
+   */
+  size_t board_row;
+  /**
+   * Column of the board, 0 to 2
+   */
+  size_t board_col;
+  /**
+   * Row of the square within the board, 0 to 2
+   */
+  size_t cell_row;
+  /**
+   * Column of the square within the board, 0 to 2
+   */
+  size_t cell_col;
+} StttMove;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Create a new game with `X` to move
+ */
+struct StttGame *sttt_game_new(void);
+
+/**
+ * Release a game. Passing `NULL` does nothing
+ *
+ * # Safety
+ * `game` must be `NULL` or a handle from this library that hasn't been freed yet
+ */
+void sttt_game_free(struct StttGame *game);
+
+/**
+ * Copy a game into a new handle
+ *
+ * # Safety
+ * `game` must be a valid handle
+ */
+struct StttGame *sttt_game_clone(const struct StttGame *game);
+
+/**
+ * Make a move for the player to move. The game is unchanged unless
+ * [`StttMoveResult::Ok`] is returned
+ *
+ * # Safety
+ * `game` must be a valid handle
+ */
+enum StttMoveResult sttt_game_make_move(struct StttGame *game,
+                                        size_t board_row,
+                                        size_t board_col,
+                                        size_t cell_row,
+                                        size_t cell_col);
+
+/**
+ * Write up to `capacity` legal moves into `moves` and return how many legal moves
+ * there are in total. A buffer of [`STTT_MAX_MOVES`] is always big enough.
+ * `moves` may be `NULL` if `capacity` is 0
+ *
+ * # Safety
+ * `game` must be a valid handle and `moves` must point to at least `capacity` writable moves
+ */
+size_t sttt_game_legal_moves(const struct StttGame *game, struct StttMove *moves, size_t capacity);
+
+/**
+ * Get the winner of the game, if any
+ *
+ * # Safety
+ * `game` must be a valid handle
+ */
+enum StttState sttt_game_get_winner(const struct StttGame *game);
+
+/**
+ * The player that will make the next move
+ *
+ * # Safety
+ * `game` must be a valid handle
+ */
+enum StttPlayer sttt_game_current_player(const struct StttGame *game);
+
+/**
+ * Write the game's position string into `buffer`, followed by a `NUL`.
+ * Returns the size needed ([`STTT_POSITION_BUFFER_SIZE`]); nothing is written
+ * if `size` is smaller than that
+ *
+ * # Safety
+ * `game` must be a valid handle and `buffer` must point to at least `size` writable bytes
+ */
+size_t sttt_game_to_position(const struct StttGame *game, char *buffer, size_t size);
+
+/**
+ * Create a game from a `NUL`-terminated position string.
+ * Returns `NULL` if the string isn't a valid position
+ *
+ * # Safety
+ * `position` must be a valid `NUL`-terminated string
+ */
+struct StttGame *sttt_game_from_position(const char *position);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* SUPER_TTT_H */
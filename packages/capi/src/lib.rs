@@ -0,0 +1,297 @@
+//! # Super Tic Tac Toe C API
+//! A stable C interface to [`super_ttt`], so frontends written in other languages
+//! can use the same rules engine. The header, `include/super_ttt.h`, is generated from this
+//! crate by cbindgen. Run `STTT_UPDATE_HEADER=1 cargo build -p super-ttt-capi` to update it
+//! after changing the API.
+//!
+//! Games are handed out as opaque [`StttGame`] pointers. Every handle returned by
+//! [`sttt_game_new`], [`sttt_game_clone`] or [`sttt_game_from_position`] must be
+//! released with [`sttt_game_free`].
+#![warn(missing_docs)]
+
+use std::ffi::{c_char, CStr};
+use std::ptr;
+
+use super_ttt::{errors::InvalidMoveError, Game, GameState, Player};
+
+/// The most legal moves a position can have
+pub const STTT_MAX_MOVES: usize = 81;
+/// The size of the buffer needed by [`sttt_game_to_position`], including the trailing `NUL`
+pub const STTT_POSITION_BUFFER_SIZE: usize = 94;
+
+/// An opaque handle to a game
+pub struct StttGame(Game);
+
+/// The result of [`sttt_game_make_move`]. Every error matches an
+/// [`InvalidMoveError`] variant of the same name
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StttMoveResult {
+    /// The move was made
+    Ok = 0,
+    /// The specified cell is already occupied
+    CellAlreadyOccupied = 1,
+    /// The specified board does not match the coordinates of the opponent's last move
+    InvalidBoard = 2,
+    /// One of the coordinates is not between 0 and 2
+    OutOfBounds = 3,
+}
+
+impl From<Result<Game, InvalidMoveError>> for StttMoveResult {
+    fn from(result: Result<Game, InvalidMoveError>) -> Self {
+        match result {
+            Ok(_) => StttMoveResult::Ok,
+            Err(InvalidMoveError::CellAlreadyOccupied) => StttMoveResult::CellAlreadyOccupied,
+            Err(InvalidMoveError::InvalidBoard) => StttMoveResult::InvalidBoard,
+            Err(InvalidMoveError::OutOfBounds) => StttMoveResult::OutOfBounds,
+        }
+    }
+}
+
+/// A player
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StttPlayer {
+    /// `X`, who always moves first
+    X = 0,
+    /// `O`
+    O = 1,
+}
+
+/// The state of a game, as returned by [`sttt_game_get_winner`]
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StttState {
+    /// Nobody has won yet and there are moves left
+    InProgress = 0,
+    /// `X` won
+    XWon = 1,
+    /// `O` won
+    OWon = 2,
+    /// Every board is finished and nobody won
+    Tie = 3,
+}
+
+/// A move, in the same order as the arguments of [`sttt_game_make_move`]
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StttMove {
+    /// Row of the board, 0 to 2
+    pub board_row: usize,
+    /// Column of the board, 0 to 2
+    pub board_col: usize,
+    /// Row of the square within the board, 0 to 2
+    pub cell_row: usize,
+    /// Column of the square within the board, 0 to 2
+    pub cell_col: usize,
+}
+
+fn into_handle(game: Game) -> *mut StttGame {
+    Box::into_raw(Box::new(StttGame(game)))
+}
+
+/// Create a new game with `X` to move
+#[no_mangle]
+pub extern "C" fn sttt_game_new() -> *mut StttGame {
+    into_handle(Game::new())
+}
+
+/// Release a game. Passing `NULL` does nothing
+///
+/// # Safety
+/// `game` must be `NULL` or a handle from this library that hasn't been freed yet
+#[no_mangle]
+pub unsafe extern "C" fn sttt_game_free(game: *mut StttGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Copy a game into a new handle
+///
+/// # Safety
+/// `game` must be a valid handle
+#[no_mangle]
+pub unsafe extern "C" fn sttt_game_clone(game: *const StttGame) -> *mut StttGame {
+    into_handle((*game).0)
+}
+
+/// Make a move for the player to move. The game is unchanged unless
+/// [`StttMoveResult::Ok`] is returned
+///
+/// # Safety
+/// `game` must be a valid handle
+#[no_mangle]
+pub unsafe extern "C" fn sttt_game_make_move(
+    game: *mut StttGame,
+    board_row: usize,
+    board_col: usize,
+    cell_row: usize,
+    cell_col: usize,
+) -> StttMoveResult {
+    (*game)
+        .0
+        .make_move(board_row, board_col, cell_row, cell_col)
+        .into()
+}
+
+/// Write up to `capacity` legal moves into `moves` and return how many legal moves
+/// there are in total. A buffer of [`STTT_MAX_MOVES`] is always big enough.
+/// `moves` may be `NULL` if `capacity` is 0
+///
+/// # Safety
+/// `game` must be a valid handle and `moves` must point to at least `capacity` writable moves
+#[no_mangle]
+pub unsafe extern "C" fn sttt_game_legal_moves(
+    game: *const StttGame,
+    moves: *mut StttMove,
+    capacity: usize,
+) -> usize {
+    let legal_moves = (*game).0.legal_moves();
+    for (i, &(board_row, board_col, cell_row, cell_col)) in
+        legal_moves.iter().take(capacity).enumerate()
+    {
+        moves.add(i).write(StttMove {
+            board_row,
+            board_col,
+            cell_row,
+            cell_col,
+        });
+    }
+    legal_moves.len()
+}
+
+/// Get the winner of the game, if any
+///
+/// # Safety
+/// `game` must be a valid handle
+#[no_mangle]
+pub unsafe extern "C" fn sttt_game_get_winner(game: *const StttGame) -> StttState {
+    match (*game).0.get_winner() {
+        GameState::InProgress => StttState::InProgress,
+        GameState::Winner(Player::X) => StttState::XWon,
+        GameState::Winner(Player::O) => StttState::OWon,
+        GameState::Tie => StttState::Tie,
+    }
+}
+
+/// The player that will make the next move
+///
+/// # Safety
+/// `game` must be a valid handle
+#[no_mangle]
+pub unsafe extern "C" fn sttt_game_current_player(game: *const StttGame) -> StttPlayer {
+    match (*game).0.current_player {
+        Player::X => StttPlayer::X,
+        Player::O => StttPlayer::O,
+    }
+}
+
+/// Write the game's position string into `buffer`, followed by a `NUL`.
+/// Returns the size needed ([`STTT_POSITION_BUFFER_SIZE`]); nothing is written
+/// if `size` is smaller than that
+///
+/// # Safety
+/// `game` must be a valid handle and `buffer` must point to at least `size` writable bytes
+#[no_mangle]
+pub unsafe extern "C" fn sttt_game_to_position(
+    game: *const StttGame,
+    buffer: *mut c_char,
+    size: usize,
+) -> usize {
+    let position = (*game).0.to_position_string();
+    if size >= STTT_POSITION_BUFFER_SIZE {
+        ptr::copy_nonoverlapping(position.as_ptr().cast(), buffer, position.len());
+        buffer.add(position.len()).write(0);
+    }
+    STTT_POSITION_BUFFER_SIZE
+}
+
+/// Create a game from a `NUL`-terminated position string.
+/// Returns `NULL` if the string isn't a valid position
+///
+/// # Safety
+/// `position` must be a valid `NUL`-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn sttt_game_from_position(position: *const c_char) -> *mut StttGame {
+    CStr::from_ptr(position)
+        .to_str()
+        .ok()
+        .and_then(|position| Game::from_position_string(position).ok())
+        .map_or(ptr::null_mut(), into_handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super_ttt::notation::POSITION_STRING_LEN;
+
+    #[test]
+    fn header_is_up_to_date() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/super_ttt.h"));
+        let committed = include_str!("../include/super_ttt.h");
+        assert!(
+            generated == committed,
+            "include/super_ttt.h is stale, run `STTT_UPDATE_HEADER=1 cargo build -p super-ttt-capi`"
+        );
+    }
+
+    #[test]
+    fn constants_match_the_core() {
+        assert_eq!(STTT_MAX_MOVES, super_ttt::moves::MAX_MOVES);
+        assert_eq!(STTT_POSITION_BUFFER_SIZE, POSITION_STRING_LEN + 1);
+    }
+
+    #[test]
+    fn play_through_the_c_api() {
+        unsafe {
+            let game = sttt_game_new();
+            assert_eq!(sttt_game_make_move(game, 0, 0, 1, 1), StttMoveResult::Ok);
+            assert_eq!(
+                sttt_game_make_move(game, 0, 0, 1, 1),
+                StttMoveResult::CellAlreadyOccupied
+            );
+            assert_eq!(
+                sttt_game_make_move(game, 2, 2, 0, 0),
+                StttMoveResult::InvalidBoard
+            );
+            assert_eq!(
+                sttt_game_make_move(game, 1, 1, 0, 3),
+                StttMoveResult::OutOfBounds
+            );
+            assert_eq!(sttt_game_current_player(game), StttPlayer::O);
+            assert_eq!(sttt_game_get_winner(game), StttState::InProgress);
+
+            let mut moves = [StttMove {
+                board_row: 0,
+                board_col: 0,
+                cell_row: 0,
+                cell_col: 0,
+            }; STTT_MAX_MOVES];
+            let count = sttt_game_legal_moves(game, moves.as_mut_ptr(), moves.len());
+            assert_eq!(count, 9);
+            assert!(moves[..count]
+                .iter()
+                .all(|m| (m.board_row, m.board_col) == (1, 1)));
+            assert_eq!(sttt_game_legal_moves(game, ptr::null_mut(), 0), 9);
+
+            let mut buffer = [0 as c_char; STTT_POSITION_BUFFER_SIZE];
+            assert_eq!(
+                sttt_game_to_position(game, buffer.as_mut_ptr(), buffer.len()),
+                STTT_POSITION_BUFFER_SIZE
+            );
+            let copy = sttt_game_from_position(buffer.as_ptr());
+            assert!(!copy.is_null());
+            assert_eq!((*copy).0, (*game).0);
+
+            let clone = sttt_game_clone(game);
+            assert_eq!((*clone).0, (*game).0);
+
+            assert!(sttt_game_from_position(c"not a position".as_ptr()).is_null());
+            sttt_game_free(copy);
+            sttt_game_free(clone);
+            sttt_game_free(game);
+            sttt_game_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! A compact binary encoding for storing lots of games.
+//!
+//! Every [`Game`] encodes to exactly [`ENCODED_LEN`] bytes, read as one little-endian bit string:
+//!
+//! - Bits `0..162`: two bits per square, in [square coordinate](crate::to_square_coords) order
+//!   (row by row). `0` is empty, `1` is `X` and `2` is `O`
+//! - Bit `162`: the player to move (`0` for `X`, `1` for `O`)
+//! - Bits `163..167`: the board the player to move was sent to, numbered `0` to `8`
+//!   row by row, or `15` if no move has been made yet
+//! - Bit `167`: always `0`
+//!
+//! [`Game::from_bytes`] rejects anything that doesn't follow this layout, as well as
+//! positions whose number of `X`s and `O`s couldn't have come from a real game.
+//!
+//! Files full of games are written with [`PositionWriter`] and read with [`PositionReader`].
+//! Such a file is the 8 byte [`FILE_HEADER`] followed by the encoded games back to back.
+use std::io::{self, Read, Write};
+
+use crate::errors::DecodeError;
+use crate::{Game, Player, Square, BOARD_SIZE, GRID_SIZE};
+
+/// The size of an encoded game in bytes
+pub const ENCODED_LEN: usize = 21;
+/// The first bytes of every file written by [`PositionWriter`]. The last byte is the format version
+pub const FILE_HEADER: [u8; 8] = *b"STTTPOS\x01";
+
+const SQUARE_BITS: usize = 2 * GRID_SIZE * GRID_SIZE;
+const PLAYER_BIT: usize = SQUARE_BITS;
+const BOARD_BITS: usize = PLAYER_BIT + 1;
+const NO_BOARD: u8 = 0b1111;
+
+fn read_bits(bytes: &[u8; ENCODED_LEN], offset: usize, len: usize) -> u8 {
+    (0..len).fold(0, |value, i| {
+        let bit = offset + i;
+        value | (((bytes[bit / 8] >> (bit % 8)) & 1) << i)
+    })
+}
+fn write_bits(bytes: &mut [u8; ENCODED_LEN], offset: usize, len: usize, value: u8) {
+    for i in 0..len {
+        let bit = offset + i;
+        bytes[bit / 8] |= ((value >> i) & 1) << (bit % 8);
+    }
+}
+
+impl Game {
+    /// Encode this game in the [binary format](crate::binary)
+    pub fn to_bytes(&self) -> [u8; ENCODED_LEN] {
+        let mut bytes = [0; ENCODED_LEN];
+        for (i, (_, square)) in self.iter_squares().enumerate() {
+            let value = match square {
+                Square::Empty => 0,
+                Square::Occupied(Player::X) => 1,
+                Square::Occupied(Player::O) => 2,
+            };
+            write_bits(&mut bytes, 2 * i, 2, value);
+        }
+        write_bits(
+            &mut bytes,
+            PLAYER_BIT,
+            1,
+            (self.current_player == Player::O) as u8,
+        );
+        let board = match self.last_move_cords {
+            Some((row, col)) => (row * BOARD_SIZE + col) as u8,
+            None => NO_BOARD,
+        };
+        write_bits(&mut bytes, BOARD_BITS, 4, board);
+        bytes
+    }
+    /// Decode a game from the [binary format](crate::binary)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Game, DecodeError> {
+        let bytes: &[u8; ENCODED_LEN] = bytes.try_into().map_err(|_| DecodeError::InvalidLength)?;
+        if read_bits(bytes, BOARD_BITS + 4, 1) != 0 {
+            return Err(DecodeError::InvalidPadding);
+        }
+
+        let mut game = Game::new();
+        for i in 0..GRID_SIZE * GRID_SIZE {
+            game[(i / GRID_SIZE, i % GRID_SIZE)] = match read_bits(bytes, 2 * i, 2) {
+                0 => Square::Empty,
+                1 => Square::Occupied(Player::X),
+                2 => Square::Occupied(Player::O),
+                _ => return Err(DecodeError::InvalidSquare),
+            };
+        }
+        game.current_player = match read_bits(bytes, PLAYER_BIT, 1) {
+            0 => Player::X,
+            _ => Player::O,
+        };
+        game.last_move_cords = match read_bits(bytes, BOARD_BITS, 4) {
+            NO_BOARD => None,
+            board @ 0..=8 => Some((board as usize / BOARD_SIZE, board as usize % BOARD_SIZE)),
+            _ => return Err(DecodeError::InvalidForcedBoard),
+        };
+
+        if !game.has_plausible_piece_counts() {
+            return Err(DecodeError::InvalidPieceCount);
+        }
+        Ok(game)
+    }
+}
+
+/// Writes games to a file (or anything else that implements [`Write`]) in the
+/// [binary format](crate::binary). Wrap the writer in a [`BufWriter`](std::io::BufWriter)
+/// when writing many games
+pub struct PositionWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PositionWriter<W> {
+    /// Start a new file by writing the [`FILE_HEADER`]
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(&FILE_HEADER)?;
+        Ok(PositionWriter { writer })
+    }
+    /// Continue a file that already has a header, like one opened in append mode
+    pub fn append(writer: W) -> Self {
+        PositionWriter { writer }
+    }
+    /// Write a single game
+    pub fn write(&mut self, game: &Game) -> io::Result<()> {
+        self.writer.write_all(&game.to_bytes())
+    }
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+    /// Get back the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads games written by a [`PositionWriter`]. Also an [`Iterator`] over the games.
+/// Wrap the reader in a [`BufReader`](std::io::BufReader) when reading many games
+pub struct PositionReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> PositionReader<R> {
+    /// Start reading a file, checking its [`FILE_HEADER`]
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; FILE_HEADER.len()];
+        reader.read_exact(&mut header)?;
+        if header != FILE_HEADER {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a super tic tac toe position file",
+            ));
+        }
+        Ok(PositionReader { reader })
+    }
+    /// Read the next game, or `None` at the end of the file.
+    /// A file that ends partway through a game is an [`UnexpectedEof`](io::ErrorKind::UnexpectedEof)
+    /// error, and a game that doesn't decode is an [`InvalidData`](io::ErrorKind::InvalidData) error
+    pub fn read(&mut self) -> io::Result<Option<Game>> {
+        let mut bytes = [0; ENCODED_LEN];
+        if !read_record(&mut self.reader, &mut bytes)? {
+            return Ok(None);
+        }
+        Game::from_bytes(&bytes)
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+/// Fill `buf` from `reader` like [`Read::read_exact`], for files of records back to back.
+/// Returns `false` if the reader was already at its end, and an
+/// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) error if it ends partway through `buf`.
+/// Interrupted reads are retried
+pub fn read_record(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(true)
+}
+
+impl<R: Read> Iterator for PositionReader<R> {
+    type Item = io::Result<Game>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_game() -> Game {
+        let mut game = Game::new();
+        game.make_move(0, 0, 1, 1).unwrap();
+        game.make_move(1, 1, 0, 0).unwrap();
+        game.make_move(0, 0, 2, 2).unwrap();
+        game.make_move(2, 2, 0, 2).unwrap();
+        game
+    }
+
+    #[test]
+    fn round_trip() {
+        for game in [Game::new(), sample_game()] {
+            assert_eq!(Game::from_bytes(&game.to_bytes()), Ok(game));
+        }
+    }
+
+    #[test]
+    fn invalid_bytes() {
+        let bytes = sample_game().to_bytes();
+        assert_eq!(
+            Game::from_bytes(&bytes[1..]),
+            Err(DecodeError::InvalidLength)
+        );
+
+        let mut invalid = bytes;
+        invalid[0] |= 0b11;
+        assert_eq!(Game::from_bytes(&invalid), Err(DecodeError::InvalidSquare));
+
+        let mut invalid = bytes;
+        // The sample game sends `O` to board 2, so this makes it 10
+        write_bits(&mut invalid, BOARD_BITS, 4, 0b1000);
+        assert_eq!(
+            Game::from_bytes(&invalid),
+            Err(DecodeError::InvalidForcedBoard)
+        );
+
+        let mut invalid = bytes;
+        invalid[ENCODED_LEN - 1] |= 0x80;
+        assert_eq!(Game::from_bytes(&invalid), Err(DecodeError::InvalidPadding));
+
+        let mut invalid = bytes;
+        invalid[0] ^= 1;
+        assert_eq!(
+            Game::from_bytes(&invalid),
+            Err(DecodeError::InvalidPieceCount)
+        );
+    }
+
+    #[test]
+    fn streams() {
+        let games = [Game::new(), sample_game(), sample_game()];
+        let mut writer = PositionWriter::new(Vec::new()).unwrap();
+        for game in &games {
+            writer.write(game).unwrap();
+        }
+        let file = writer.into_inner();
+        assert_eq!(file.len(), FILE_HEADER.len() + games.len() * ENCODED_LEN);
+
+        let read: Vec<Game> = PositionReader::new(file.as_slice())
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(read, games);
+
+        let mut reader = PositionReader::new(&file[..file.len() - 1]).unwrap();
+        reader.read().unwrap();
+        reader.read().unwrap();
+        assert_eq!(
+            reader.read().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert!(PositionReader::new(&file[1..]).is_err());
+    }
+}
@@ -8,6 +8,8 @@ pub enum InvalidMoveError {
     CellAlreadyOccupied,
     /// The specified board does not match the coordinates of the opponent's last move
     InvalidBoard,
+    /// One of the coordinates is not between 0 and 2
+    OutOfBounds,
 }
 
 impl fmt::Display for InvalidMoveError {
@@ -20,6 +22,79 @@ impl fmt::Display for InvalidMoveError {
                 "the specified board does not match the coordinates of the opponent's last move"
                     .fmt(f)
             }
+            InvalidMoveError::OutOfBounds => "the specified coordinates are out of bounds".fmt(f),
         }
     }
 }
+impl std::error::Error for InvalidMoveError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Text couldn't be parsed. Used by the [`FromStr`](std::str::FromStr) implementations of this crate
+/// and [`Game::from_position_string`](crate::Game::from_position_string)
+pub enum ParseError {
+    /// Expected `X` or `O`
+    InvalidPlayer,
+    /// Expected `X`, `O` or `.`
+    InvalidSquare,
+    /// Expected `1-0`, `0-1`, `½-½` or `*`
+    InvalidGameState,
+    /// A [position string](crate::notation) doesn't have the right number of fields, rows or columns
+    InvalidPositionLayout,
+    /// A [position string](crate::notation)'s board number isn't `-` or `0` to `8`
+    InvalidForcedBoard,
+    /// The number of `X`s and `O`s in a position doesn't fit the player to move
+    InvalidPieceCount,
+    /// Expected a move in [move notation](crate::notation#move-notation), like `e5`
+    InvalidMove,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidPlayer => "expected a player (`X` or `O`)".fmt(f),
+            ParseError::InvalidSquare => "expected a square (`X`, `O` or `.`)".fmt(f),
+            ParseError::InvalidGameState => {
+                "expected a game result (`1-0`, `0-1`, `½-½` or `*`)".fmt(f)
+            }
+            ParseError::InvalidPositionLayout => {
+                "expected nine rows of nine squares, the player to move and a board number".fmt(f)
+            }
+            ParseError::InvalidForcedBoard => "expected a board number (`0` to `8`) or `-`".fmt(f),
+            ParseError::InvalidPieceCount => {
+                "the number of pieces doesn't match the player to move".fmt(f)
+            }
+            ParseError::InvalidMove => "expected a move from `a1` to `i9`".fmt(f),
+        }
+    }
+}
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Bytes couldn't be decoded into a game. Used by [`Game::from_bytes`](crate::Game::from_bytes)
+pub enum DecodeError {
+    /// The input isn't exactly [`ENCODED_LEN`](crate::binary::ENCODED_LEN) bytes long
+    InvalidLength,
+    /// A square has the unused value `3`
+    InvalidSquare,
+    /// The board number isn't `0` to `8` or `15`
+    InvalidForcedBoard,
+    /// The unused bit at the end is set
+    InvalidPadding,
+    /// The number of `X`s and `O`s doesn't fit the player to move
+    InvalidPieceCount,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidLength => "an encoded game has the wrong length".fmt(f),
+            DecodeError::InvalidSquare => "an encoded square is invalid".fmt(f),
+            DecodeError::InvalidForcedBoard => "the encoded board number is invalid".fmt(f),
+            DecodeError::InvalidPadding => "the unused bits of an encoded game are set".fmt(f),
+            DecodeError::InvalidPieceCount => {
+                "the number of pieces doesn't match the player to move".fmt(f)
+            }
+        }
+    }
+}
+impl std::error::Error for DecodeError {}
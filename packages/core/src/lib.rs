@@ -16,11 +16,18 @@
 //!
 //! **Square:** a cell of a traditional tic-tac-toe board. It will either be empty or containing an `X`/`O`,
 //!
-//! **Square coordinates:** An (x, y) pair that, like a normal coordinate, represents the location of something. But unlike a regular coordinate, it represents the exact location of a specific square. X and Y will be integers between and including 0 to 8.
+//! **Square coordinates:** An (x, y) pair that, like a normal coordinate, represents the location of something. But unlike a regular coordinate, it represents the exact location of a specific square. X and Y will be integers between and including 0 to 8. See [`to_square_coords`] and [`from_square_coords`] for converting to and from coordinates.
 #![warn(missing_docs)]
 
 use std::fmt::Display;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+pub mod binary;
 pub mod errors;
+pub mod lookup;
+pub mod moves;
+pub mod notation;
+pub use moves::{Move, MoveList};
 /// Represents a player (`X` or `O`)
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 #[allow(missing_docs)]
@@ -29,6 +36,45 @@ pub enum Player {
     O,
 }
 
+impl Player {
+    /// The other player
+    pub fn opponent(self) -> Player {
+        match self {
+            Player::X => Player::O,
+            Player::O => Player::X,
+        }
+    }
+    /// `1` for [`Player::X`] and `-1` for [`Player::O`]. Useful for scoring games from `X`'s perspective
+    pub fn to_sign(self) -> i8 {
+        match self {
+            Player::X => 1,
+            Player::O => -1,
+        }
+    }
+}
+/// Formats as `X` or `O`
+impl Display for Player {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Player::X => "X",
+            Player::O => "O",
+        }
+        .fmt(f)
+    }
+}
+/// Parses `X` or `O` (case insensitive)
+impl FromStr for Player {
+    type Err = errors::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "X" | "x" => Ok(Player::X),
+            "O" | "o" => Ok(Player::O),
+            _ => Err(errors::ParseError::InvalidPlayer),
+        }
+    }
+}
+
 /// Represents a the content of a smaller Tic Tac Toe board
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
 #[allow(missing_docs)]
@@ -38,6 +84,30 @@ pub enum Square {
     Occupied(Player),
 }
 
+/// Formats as `.` for an empty square, otherwise as the player occupying it
+impl Display for Square {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Square::Empty => ".".fmt(f),
+            Square::Occupied(player) => player.fmt(f),
+        }
+    }
+}
+/// Parses the output of [`Square`]'s [`Display`] implementation
+impl FromStr for Square {
+    type Err = errors::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "." => Ok(Square::Empty),
+            _ => s
+                .parse()
+                .map(Square::Occupied)
+                .map_err(|_| errors::ParseError::InvalidSquare),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 #[allow(missing_docs)]
 pub enum GameState {
@@ -45,9 +115,64 @@ pub enum GameState {
     Winner(Player),
     InProgress,
 }
+/// Formats like a chess result: `1-0` if `X` won, `0-1` if `O` won,
+/// `½-½` for a tie and `*` if the game is still going
+impl Display for GameState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameState::Winner(Player::X) => "1-0",
+            GameState::Winner(Player::O) => "0-1",
+            GameState::Tie => "½-½",
+            GameState::InProgress => "*",
+        }
+        .fmt(f)
+    }
+}
+/// Parses the output of [`GameState`]'s [`Display`] implementation.
+/// `1/2-1/2` is also accepted for a tie
+impl FromStr for GameState {
+    type Err = errors::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1-0" => Ok(GameState::Winner(Player::X)),
+            "0-1" => Ok(GameState::Winner(Player::O)),
+            "½-½" | "1/2-1/2" => Ok(GameState::Tie),
+            "*" => Ok(GameState::InProgress),
+            _ => Err(errors::ParseError::InvalidGameState),
+        }
+    }
+}
 
 /// The size length of the board *and* the game. This should never change.
 pub const BOARD_SIZE: usize = 3;
+/// The side length of the whole game in squares (9)
+pub const GRID_SIZE: usize = BOARD_SIZE * BOARD_SIZE;
+
+/// Convert a board's coordinates and a square's coordinates within that board
+/// into square coordinates
+pub fn to_square_coords(
+    board_row: usize,
+    board_col: usize,
+    cell_row: usize,
+    cell_col: usize,
+) -> (usize, usize) {
+    (
+        board_row * BOARD_SIZE + cell_row,
+        board_col * BOARD_SIZE + cell_col,
+    )
+}
+/// Convert square coordinates into `(board_row, board_col, cell_row, cell_col)`,
+/// the form [`Game::make_move`] accepts. The inverse of [`to_square_coords`]
+pub fn from_square_coords(row: usize, col: usize) -> (usize, usize, usize, usize) {
+    (
+        row / BOARD_SIZE,
+        col / BOARD_SIZE,
+        row % BOARD_SIZE,
+        col % BOARD_SIZE,
+    )
+}
+
 /// Represents the 3x3 traditional Tic Tac Toe board
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
 pub struct Board {
@@ -56,7 +181,16 @@ pub struct Board {
 }
 
 impl Board {
-    fn check_winner(&self, player: Player) -> bool {
+    /// Iterate over every square of the board along with its coordinates, row by row
+    pub fn iter_squares(&self) -> impl Iterator<Item = ((usize, usize), Square)> + '_ {
+        self.squares.iter().enumerate().flat_map(|(row, squares)| {
+            squares
+                .iter()
+                .enumerate()
+                .map(move |(col, &square)| ((row, col), square))
+        })
+    }
+    pub(crate) fn check_winner(&self, player: Player) -> bool {
         // Check rows, columns, and diagonals for a win within a 3x3 cell
         (0..BOARD_SIZE)
             .any(|i| (0..BOARD_SIZE).all(|j| self.squares[i][j] == Square::Occupied(player)))
@@ -65,8 +199,12 @@ impl Board {
             || (0..BOARD_SIZE).all(|i| self.squares[i][i] == Square::Occupied(player))
             || (0..BOARD_SIZE).all(|i| self.squares[i][2 - i] == Square::Occupied(player))
     }
-    /// Get the winner of the game, if any
+    /// Get the winner of the game, if any. This is a [table lookup](lookup)
     pub fn get_winner(&self) -> GameState {
+        self.info().state
+    }
+    /// Work out the winner by scanning the board. Used to build the [lookup table](lookup)
+    pub(crate) fn compute_winner(&self) -> GameState {
         if self.check_winner(Player::O) {
             return GameState::Winner(Player::O);
         }
@@ -100,7 +238,7 @@ impl Board {
 /// game.make_move(0, 2, 1, 0).unwrap();
 /// match game.get_winner() {
 ///     super_ttt::GameState::Winner(player) => {
-///         println!("Player {:?} wins!", player);
+///         println!("Player {} wins!", player);
 ///     }
 ///     super_ttt::GameState::Tie => {
 ///         println!("It's a tie!");
@@ -128,6 +266,23 @@ impl Default for Game {
         Self::new()
     }
 }
+/// Access a square by its square coordinates
+impl Index<(usize, usize)> for Game {
+    type Output = Square;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Square {
+        let (board_row, board_col, cell_row, cell_col) = from_square_coords(row, col);
+        &self.boards[board_row][board_col].squares[cell_row][cell_col]
+    }
+}
+/// Mutably access a square by its square coordinates. This does not check
+/// the rules of the game nor change [`Game::current_player`]
+impl IndexMut<(usize, usize)> for Game {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Square {
+        let (board_row, board_col, cell_row, cell_col) = from_square_coords(row, col);
+        &mut self.boards[board_row][board_col].squares[cell_row][cell_col]
+    }
+}
 impl Display for Game {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "┏━━━┳━━━┳━━━┓")?;
@@ -175,6 +330,12 @@ impl Game {
         cell_col: usize,
     ) -> Result<Self, errors::InvalidMoveError> {
         // Check if the move is valid
+        if [board_row, board_col, cell_row, cell_col]
+            .iter()
+            .any(|&cord| cord >= BOARD_SIZE)
+        {
+            return Err(errors::InvalidMoveError::OutOfBounds);
+        }
         if self.boards[board_row][board_col].squares[cell_row][cell_col] != Square::Empty {
             return Err(errors::InvalidMoveError::CellAlreadyOccupied);
         }
@@ -202,16 +363,46 @@ impl Game {
             Square::Occupied(self.current_player);
 
         // Switch to the next player
-        self.current_player = match self.current_player {
-            Player::X => Player::O,
-            Player::O => Player::X,
-        };
+        self.current_player = self.current_player.opponent();
 
         self.last_move_cords = Some((cell_row, cell_col));
 
         Ok(*self)
     }
 
+    /// Get the square at the given square coordinates
+    pub fn square_at(&self, row: usize, col: usize) -> Square {
+        self[(row, col)]
+    }
+    /// Iterate over all 81 squares along with their square coordinates,
+    /// row by row across the whole game
+    pub fn iter_squares(&self) -> impl Iterator<Item = ((usize, usize), Square)> + '_ {
+        (0..GRID_SIZE)
+            .flat_map(|row| (0..GRID_SIZE).map(move |col| (row, col)))
+            .map(|cords| (cords, self[cords]))
+    }
+    /// Like [`Game::iter_squares`], but yields every square as
+    /// `((board_row, board_col, cell_row, cell_col), square)`, board by board
+    pub fn iter_board_squares(
+        &self,
+    ) -> impl Iterator<Item = ((usize, usize, usize, usize), Square)> + '_ {
+        self.boards
+            .iter()
+            .enumerate()
+            .flat_map(|(board_row, boards)| {
+                boards
+                    .iter()
+                    .enumerate()
+                    .flat_map(move |(board_col, board)| {
+                        board
+                            .iter_squares()
+                            .map(move |((cell_row, cell_col), square)| {
+                                ((board_row, board_col, cell_row, cell_col), square)
+                            })
+                    })
+            })
+    }
+
     /// Check if any of the boards has a winner
     fn check_winner(&self, player: Player) -> bool {
         // Columns
@@ -252,4 +443,83 @@ mod tests {
     fn game_struct_size() {
         assert_eq!(mem::size_of::<Game>(), 112);
     }
+
+    #[test]
+    fn out_of_bounds_move() {
+        let mut game = Game::new();
+        assert_eq!(
+            game.make_move(0, 3, 0, 0),
+            Err(errors::InvalidMoveError::OutOfBounds)
+        );
+        assert_eq!(game, Game::new());
+    }
+
+    #[test]
+    fn text_round_trip() {
+        for player in [Player::X, Player::O] {
+            assert_eq!(player.to_string().parse(), Ok(player));
+            assert_eq!(player.opponent().opponent(), player);
+            assert_eq!(player.to_sign(), -player.opponent().to_sign());
+        }
+        for square in [
+            Square::Empty,
+            Square::Occupied(Player::X),
+            Square::Occupied(Player::O),
+        ] {
+            assert_eq!(square.to_string().parse(), Ok(square));
+        }
+        for state in [
+            GameState::Tie,
+            GameState::Winner(Player::X),
+            GameState::Winner(Player::O),
+            GameState::InProgress,
+        ] {
+            assert_eq!(state.to_string().parse(), Ok(state));
+        }
+        assert_eq!(GameState::Tie.to_string(), "½-½");
+        assert_eq!("1/2-1/2".parse(), Ok(GameState::Tie));
+        assert_eq!(
+            "-".parse::<Square>(),
+            Err(errors::ParseError::InvalidSquare)
+        );
+        assert_eq!(
+            "XO".parse::<Player>(),
+            Err(errors::ParseError::InvalidPlayer)
+        );
+    }
+
+    #[test]
+    fn square_coords_round_trip() {
+        for row in 0..GRID_SIZE {
+            for col in 0..GRID_SIZE {
+                let (board_row, board_col, cell_row, cell_col) = from_square_coords(row, col);
+                assert_eq!(
+                    to_square_coords(board_row, board_col, cell_row, cell_col),
+                    (row, col)
+                );
+            }
+        }
+        assert_eq!(from_square_coords(4, 7), (1, 2, 1, 1));
+    }
+
+    #[test]
+    fn square_access() {
+        let mut game = Game::new();
+        game.make_move(0, 2, 1, 1).unwrap();
+        assert_eq!(game.square_at(1, 7), Square::Occupied(Player::X));
+        assert_eq!(game[(1, 7)], Square::Occupied(Player::X));
+        game[(8, 8)] = Square::Occupied(Player::O);
+        assert_eq!(game.boards[2][2].squares[2][2], Square::Occupied(Player::O));
+
+        let occupied: Vec<_> = game
+            .iter_squares()
+            .filter(|(_, square)| *square != Square::Empty)
+            .map(|(cords, _)| cords)
+            .collect();
+        assert_eq!(occupied, vec![(1, 7), (8, 8)]);
+        assert_eq!(game.iter_squares().count(), 81);
+        assert!(game
+            .iter_board_squares()
+            .all(|((br, bc, cr, cc), square)| game[to_square_coords(br, bc, cr, cc)] == square));
+    }
 }
@@ -0,0 +1,172 @@
+//! A precomputed table describing every possible [`Board`].
+//!
+//! A small board has only 3<sup>9</sup> = 19,683 configurations, so instead of walking
+//! rows, columns and diagonals each time we need to know something about a board, we
+//! work it out once for all of them and look it up afterwards. The table is built
+//! lazily the first time it is used.
+//!
+//! Boards are indexed by [`Board::encode`]: each square is a base-3 digit
+//! (`0` for [`Square::Empty`], `1` for `X`, `2` for `O`), with the top left square
+//! being the least significant digit and the bottom right square the most significant.
+use std::sync::OnceLock;
+
+use crate::{Board, GameState, Player, Square, BOARD_SIZE};
+
+/// The number of distinct small board configurations (3<sup>9</sup>)
+pub const BOARD_CONFIGURATIONS: usize = 19_683;
+
+/// Everything the table knows about a single board configuration.
+///
+/// Square masks use bit `row * 3 + col` for the square at `(row, col)`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BoardInfo {
+    /// The same value [`Board::get_winner`] returns
+    pub state: GameState,
+    /// Mask of the squares that are still empty
+    pub empty: u16,
+    /// Mask of the empty squares where `X` would immediately win the board.
+    /// Always `0` if the board is already finished.
+    pub x_wins: u16,
+    /// Mask of the empty squares where `O` would immediately win the board.
+    /// Always `0` if the board is already finished.
+    pub o_wins: u16,
+}
+
+impl BoardInfo {
+    /// The number of empty squares left on the board
+    pub fn empty_count(&self) -> u32 {
+        self.empty.count_ones()
+    }
+    /// Mask of the squares where `player` would immediately win the board
+    pub fn winning_squares(&self, player: Player) -> u16 {
+        match player {
+            Player::X => self.x_wins,
+            Player::O => self.o_wins,
+        }
+    }
+    /// Whether `player` can win the board in a single move
+    pub fn can_win(&self, player: Player) -> bool {
+        self.winning_squares(player) != 0
+    }
+}
+
+static TABLE: OnceLock<Box<[BoardInfo]>> = OnceLock::new();
+
+/// Look up the precomputed facts for a board encoding made by [`Board::encode`].
+///
+/// # Panics
+/// Panics if `index` is not below [`BOARD_CONFIGURATIONS`]
+pub fn info(index: u16) -> &'static BoardInfo {
+    &TABLE.get_or_init(build)[index as usize]
+}
+
+fn build() -> Box<[BoardInfo]> {
+    (0..BOARD_CONFIGURATIONS as u16)
+        .map(|index| {
+            let board = Board::decode(index);
+            let state = board.compute_winner();
+            let mut info = BoardInfo {
+                state,
+                empty: 0,
+                x_wins: 0,
+                o_wins: 0,
+            };
+            for (bit, (row, col)) in squares().enumerate() {
+                if board.squares[row][col] != Square::Empty {
+                    continue;
+                }
+                info.empty |= 1 << bit;
+                if state != GameState::InProgress {
+                    continue;
+                }
+                for (player, mask) in [(Player::X, &mut info.x_wins), (Player::O, &mut info.o_wins)]
+                {
+                    let mut next = board;
+                    next.squares[row][col] = Square::Occupied(player);
+                    if next.check_winner(player) {
+                        *mask |= 1 << bit;
+                    }
+                }
+            }
+            info
+        })
+        .collect()
+}
+
+/// Every `(row, col)` pair of a board, in encoding order
+fn squares() -> impl Iterator<Item = (usize, usize)> {
+    (0..BOARD_SIZE).flat_map(|row| (0..BOARD_SIZE).map(move |col| (row, col)))
+}
+
+impl Board {
+    /// Encode this board as an index into the [lookup table](crate::lookup)
+    pub fn encode(&self) -> u16 {
+        self.squares
+            .iter()
+            .flatten()
+            .rev()
+            .fold(0, |index, square| {
+                index * 3
+                    + match square {
+                        Square::Empty => 0,
+                        Square::Occupied(Player::X) => 1,
+                        Square::Occupied(Player::O) => 2,
+                    }
+            })
+    }
+    /// The inverse of [`Board::encode`]. Digits beyond the ninth are ignored.
+    pub fn decode(mut index: u16) -> Self {
+        let mut board = Board::default();
+        for (row, col) in squares() {
+            board.squares[row][col] = match index % 3 {
+                0 => Square::Empty,
+                1 => Square::Occupied(Player::X),
+                _ => Square::Occupied(Player::O),
+            };
+            index /= 3;
+        }
+        board
+    }
+    /// Look up everything the [table](crate::lookup) knows about this board
+    pub fn info(&self) -> &'static BoardInfo {
+        info(self.encode())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding_round_trips() {
+        for index in 0..BOARD_CONFIGURATIONS as u16 {
+            assert_eq!(Board::decode(index).encode(), index);
+        }
+    }
+
+    #[test]
+    fn table_matches_scanning() {
+        for index in 0..BOARD_CONFIGURATIONS as u16 {
+            let board = Board::decode(index);
+            assert_eq!(info(index).state, board.compute_winner());
+        }
+    }
+
+    #[test]
+    fn one_move_wins() {
+        // X X .
+        // O O .
+        // . . .
+        let mut board = Board::default();
+        board.squares[0][0] = Square::Occupied(Player::X);
+        board.squares[0][1] = Square::Occupied(Player::X);
+        board.squares[1][0] = Square::Occupied(Player::O);
+        board.squares[1][1] = Square::Occupied(Player::O);
+        let info = board.info();
+        assert_eq!(info.state, GameState::InProgress);
+        assert_eq!(info.empty_count(), 5);
+        assert_eq!(info.x_wins, 1 << 2);
+        assert_eq!(info.o_wins, 1 << 5);
+        assert!(info.can_win(Player::X) && info.can_win(Player::O));
+    }
+}
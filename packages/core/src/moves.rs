@@ -0,0 +1,150 @@
+//! Move generation without heap allocations.
+//!
+//! There can never be more than 81 legal moves, so [`MoveList`] keeps them
+//! in a fixed-size array that lives on the stack.
+use std::fmt;
+use std::ops::Deref;
+
+use crate::{Game, GameState, BOARD_SIZE, GRID_SIZE};
+
+/// A move in the form [`Game::make_move`] accepts: `(board_row, board_col, cell_row, cell_col)`
+pub type Move = (usize, usize, usize, usize);
+
+/// The most moves a position can ever have (every square of an empty game)
+pub const MAX_MOVES: usize = GRID_SIZE * GRID_SIZE;
+
+/// A stack-allocated list of up to [`MAX_MOVES`] moves.
+///
+/// Dereferences to a slice, so everything that works on `&[Move]` works here too.
+#[derive(Copy, Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    /// Create an empty list
+    pub const fn new() -> Self {
+        MoveList {
+            moves: [(0, 0, 0, 0); MAX_MOVES],
+            len: 0,
+        }
+    }
+    /// Add a move to the end of the list
+    ///
+    /// # Panics
+    /// Panics if the list already holds [`MAX_MOVES`] moves
+    pub fn push(&mut self, move_: Move) {
+        self.moves[self.len] = move_;
+        self.len += 1;
+    }
+    /// Remove the last move from the list and return it, or `None` if it's empty
+    pub fn pop(&mut self) -> Option<Move> {
+        self.len = self.len.checked_sub(1)?;
+        Some(self.moves[self.len])
+    }
+    /// Remove every move from the list
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+    /// The moves as a slice
+    pub fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        self.as_slice()
+    }
+}
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+impl PartialEq for MoveList {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+impl Eq for MoveList {}
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl Game {
+    /// Every move [`Game::make_move`] would accept in this position.
+    ///
+    /// This does not check whether the game is already over; use [`Game::get_winner`] for that.
+    pub fn legal_moves(&self) -> MoveList {
+        let mut moves = MoveList::new();
+        self.legal_moves_into(&mut moves);
+        moves
+    }
+    /// Like [`Game::legal_moves`], but fills a list owned by the caller so it can be reused.
+    /// The list is cleared first
+    pub fn legal_moves_into(&self, moves: &mut MoveList) {
+        moves.clear();
+        let mut row_range = 0..BOARD_SIZE;
+        let mut col_range = 0..BOARD_SIZE;
+
+        if let Some((row, col)) = self.last_move_cords {
+            if self.boards[row][col].get_winner() == GameState::InProgress {
+                row_range = row..row + 1;
+                col_range = col..col + 1;
+            }
+        }
+
+        for board_row in row_range {
+            for board_col in col_range.clone() {
+                let mut empty = self.boards[board_row][board_col].info().empty;
+                while empty != 0 {
+                    let bit = empty.trailing_zeros() as usize;
+                    moves.push((board_row, board_col, bit / BOARD_SIZE, bit % BOARD_SIZE));
+                    empty &= empty - 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_square_is_legal_at_the_start() {
+        assert_eq!(Game::new().legal_moves().len(), MAX_MOVES);
+    }
+
+    #[test]
+    fn forced_board() {
+        let mut game = Game::new();
+        game.make_move(1, 1, 0, 2).unwrap();
+        let moves = game.legal_moves();
+        assert_eq!(moves.len(), 9);
+        assert!(moves.iter().all(|&(br, bc, _, _)| (br, bc) == (0, 2)));
+
+        let mut reused = MoveList::new();
+        reused.push((2, 2, 2, 2));
+        game.legal_moves_into(&mut reused);
+        assert_eq!(reused, moves);
+        for &(br, bc, cr, cc) in &moves {
+            let mut next = game;
+            assert!(next.make_move(br, bc, cr, cc).is_ok());
+        }
+    }
+}
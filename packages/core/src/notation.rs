@@ -0,0 +1,196 @@
+//! Text formats for sharing games.
+//!
+//! ## Position strings
+//! A position string describes a [`Game`] exactly, in the spirit of chess' FEN.
+//! It has three fields separated by a single space:
+//!
+//! 1. The squares, as nine rows of nine [`Square`]s (`X`, `O` or `.`) separated by `/`.
+//!    Rows go from top to bottom, so the rows are indexed by [square coordinates](crate::to_square_coords)
+//! 2. The player to move (`X` or `O`)
+//! 3. The board the player to move was sent to, numbered `0` to `8` row by row,
+//!    or `-` if no move has been made yet. This is [`Game::last_move_cords`]
+//!
+//! The starting position is
+//! ```text
+//! ........./........./........./........./........./........./........./........./......... X -
+//! ```
+//!
+//! ## Move notation
+//! A move is written as the square it's played on: a column letter from `a` to `i`
+//! (left to right) followed by a row number from `1` to `9` (top to bottom).
+//! `a1` is the top left square, `e5` the centre and `i9` the bottom right square.
+//! See [`move_to_string`] and [`parse_move`].
+use crate::errors::ParseError;
+use crate::{
+    from_square_coords, to_square_coords, Game, Move, Player, Square, BOARD_SIZE, GRID_SIZE,
+};
+
+/// The length of every position string
+pub const POSITION_STRING_LEN: usize = GRID_SIZE * GRID_SIZE + (GRID_SIZE - 1) + 4;
+
+impl Game {
+    /// Write this game as a [position string](crate::notation)
+    pub fn to_position_string(&self) -> String {
+        let mut position = String::with_capacity(POSITION_STRING_LEN);
+        for row in 0..GRID_SIZE {
+            if row != 0 {
+                position.push('/');
+            }
+            for col in 0..GRID_SIZE {
+                position.push_str(&self[(row, col)].to_string());
+            }
+        }
+        position.push(' ');
+        position.push_str(&self.current_player.to_string());
+        position.push(' ');
+        match self.last_move_cords {
+            Some((row, col)) => position.push_str(&(row * BOARD_SIZE + col).to_string()),
+            None => position.push('-'),
+        }
+        position
+    }
+    /// Read a game from a [position string](crate::notation).
+    ///
+    /// Besides the format itself, this checks that the number of `X`s and `O`s
+    /// could have come from a real game with the given player to move
+    pub fn from_position_string(position: &str) -> Result<Game, ParseError> {
+        let mut fields = position.split(' ');
+        let (Some(squares), Some(player), Some(board), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(ParseError::InvalidPositionLayout);
+        };
+
+        let mut game = Game::new();
+        let rows: Vec<&str> = squares.split('/').collect();
+        if rows.len() != GRID_SIZE {
+            return Err(ParseError::InvalidPositionLayout);
+        }
+        for (row, squares) in rows.into_iter().enumerate() {
+            if squares.chars().count() != GRID_SIZE {
+                return Err(ParseError::InvalidPositionLayout);
+            }
+            for (col, square) in squares.chars().enumerate() {
+                game[(row, col)] = square.encode_utf8(&mut [0; 4]).parse()?;
+            }
+        }
+
+        game.current_player = player.parse()?;
+        game.last_move_cords = match board.as_bytes() {
+            b"-" => None,
+            &[digit @ b'0'..=b'8'] => {
+                let board = (digit - b'0') as usize;
+                Some((board / BOARD_SIZE, board % BOARD_SIZE))
+            }
+            _ => return Err(ParseError::InvalidForcedBoard),
+        };
+
+        if !game.has_plausible_piece_counts() {
+            return Err(ParseError::InvalidPieceCount);
+        }
+        Ok(game)
+    }
+
+    /// Whether the number of `X`s and `O`s could have come from a real game
+    /// with [`Game::current_player`] to move. `X` always starts
+    pub(crate) fn has_plausible_piece_counts(&self) -> bool {
+        let count = |player| {
+            self.iter_squares()
+                .filter(|&(_, square)| square == Square::Occupied(player))
+                .count()
+        };
+        let (xs, os) = (count(Player::X), count(Player::O));
+        let expected_xs = match self.current_player {
+            Player::X => os,
+            Player::O => os + 1,
+        };
+        xs == expected_xs && (self.last_move_cords.is_some() || xs + os == 0)
+    }
+}
+
+/// Write a move in [move notation](crate::notation#move-notation)
+pub fn move_to_string((board_row, board_col, cell_row, cell_col): Move) -> String {
+    let (row, col) = to_square_coords(board_row, board_col, cell_row, cell_col);
+    format!("{}{}", (b'a' + col as u8) as char, row + 1)
+}
+/// Read a move written in [move notation](crate::notation#move-notation)
+pub fn parse_move(notation: &str) -> Result<Move, ParseError> {
+    match notation.as_bytes() {
+        &[col @ b'a'..=b'i', row @ b'1'..=b'9'] => Ok(from_square_coords(
+            (row - b'1') as usize,
+            (col - b'a') as usize,
+        )),
+        _ => Err(ParseError::InvalidMove),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "........./........./........./........./........./........./........./........./......... X -";
+
+    #[test]
+    fn starting_position() {
+        assert_eq!(Game::new().to_position_string(), START);
+        assert_eq!(START.len(), POSITION_STRING_LEN);
+        assert_eq!(Game::from_position_string(START), Ok(Game::new()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut game = Game::new();
+        game.make_move(0, 0, 1, 1).unwrap();
+        game.make_move(1, 1, 0, 0).unwrap();
+        game.make_move(0, 0, 2, 2).unwrap();
+        let position = game.to_position_string();
+        assert_eq!(
+            position,
+            "........./.X......./..X....../...O...../........./........./........./........./......... O 8"
+        );
+        assert_eq!(Game::from_position_string(&position), Ok(game));
+    }
+
+    #[test]
+    fn move_notation() {
+        assert_eq!(move_to_string((0, 0, 0, 0)), "a1");
+        assert_eq!(move_to_string((1, 1, 1, 1)), "e5");
+        assert_eq!(move_to_string((0, 2, 1, 0)), "g2");
+        for &move_ in &Game::new().legal_moves() {
+            assert_eq!(parse_move(&move_to_string(move_)), Ok(move_));
+        }
+        for invalid in ["", "a", "a0", "j1", "A1", "a10", "1a"] {
+            assert_eq!(parse_move(invalid), Err(ParseError::InvalidMove));
+        }
+    }
+
+    #[test]
+    fn invalid_positions() {
+        let parse = |position: &str| Game::from_position_string(position).unwrap_err();
+        assert_eq!(parse("X -"), ParseError::InvalidPositionLayout);
+        assert_eq!(
+            parse(&START.replace("X -", "X - extra")),
+            ParseError::InvalidPositionLayout
+        );
+        assert_eq!(
+            parse(&START.replacen(".........", "........", 1)),
+            ParseError::InvalidPositionLayout
+        );
+        assert_eq!(
+            parse(&START.replacen('.', "?", 1)),
+            ParseError::InvalidSquare
+        );
+        assert_eq!(
+            parse(&START.replace("X -", "Z -")),
+            ParseError::InvalidPlayer
+        );
+        assert_eq!(
+            parse(&START.replace("X -", "X 9")),
+            ParseError::InvalidForcedBoard
+        );
+        assert_eq!(
+            parse(&START.replace("X -", "O -")),
+            ParseError::InvalidPieceCount
+        );
+    }
+}
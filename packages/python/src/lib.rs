@@ -0,0 +1,180 @@
+//! # Super Tic Tac Toe for Python
+//! [PyO3](https://pyo3.rs) bindings for [`super_ttt`]. Build a wheel with
+//! [maturin](https://www.maturin.rs): `maturin build -m packages/python/Cargo.toml`
+#![warn(missing_docs)]
+use numpy::{PyArray1, PyArray3, PyArrayMethods};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::{create_exception, types::PyType};
+use super_ttt::{GameState, Square, GRID_SIZE};
+
+create_exception!(
+    super_ttt,
+    InvalidMoveError,
+    PyValueError,
+    "The move isn't allowed in this position"
+);
+
+/// A player (`X` or `O`)
+#[pyclass(eq, eq_int, hash, frozen, module = "super_ttt")]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Player {
+    /// The player who moves first
+    X,
+    /// The player who moves second
+    O,
+}
+
+impl From<super_ttt::Player> for Player {
+    fn from(player: super_ttt::Player) -> Self {
+        match player {
+            super_ttt::Player::X => Player::X,
+            super_ttt::Player::O => Player::O,
+        }
+    }
+}
+
+#[pymethods]
+impl Player {
+    /// The other player
+    fn opponent(&self) -> Player {
+        match self {
+            Player::X => Player::O,
+            Player::O => Player::X,
+        }
+    }
+    /// `"X"` or `"O"`
+    fn __str__(&self) -> &'static str {
+        match self {
+            Player::X => "X",
+            Player::O => "O",
+        }
+    }
+}
+
+/// The 9x9 super tic tac toe game. `X` starts
+#[pyclass(eq, module = "super_ttt")]
+#[derive(Clone, PartialEq, Eq)]
+pub struct Game(super_ttt::Game);
+
+#[pymethods]
+impl Game {
+    /// A game with no moves played yet
+    #[new]
+    fn new() -> Self {
+        Game(super_ttt::Game::new())
+    }
+    /// Read a game from a position string. Raises `ValueError` if it isn't valid
+    #[classmethod]
+    fn from_position(_cls: &Bound<'_, PyType>, position: &str) -> PyResult<Self> {
+        super_ttt::Game::from_position_string(position)
+            .map(Game)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+    /// Write this game as a position string
+    fn to_position(&self) -> String {
+        self.0.to_position_string()
+    }
+    /// Make a move for the current player. Raises `InvalidMoveError` if it isn't allowed
+    fn make_move(
+        &mut self,
+        board_row: usize,
+        board_col: usize,
+        cell_row: usize,
+        cell_col: usize,
+    ) -> PyResult<()> {
+        self.0
+            .make_move(board_row, board_col, cell_row, cell_col)
+            .map(|_| ())
+            .map_err(|err| InvalidMoveError::new_err(err.to_string()))
+    }
+    /// Every move `make_move` accepts, as `(board_row, board_col, cell_row, cell_col)` tuples
+    fn legal_moves(&self) -> Vec<(usize, usize, usize, usize)> {
+        self.0.legal_moves().to_vec()
+    }
+    /// The winner, or `None` if the game is tied or still in progress
+    fn winner(&self) -> Option<Player> {
+        match self.0.get_winner() {
+            GameState::Winner(player) => Some(player.into()),
+            _ => None,
+        }
+    }
+    /// The result of the game: `"1-0"` if `X` won, `"0-1"` if `O` won,
+    /// `"½-½"` for a tie and `"*"` if it's still in progress
+    fn result(&self) -> String {
+        self.0.get_winner().to_string()
+    }
+    /// Whether the game has finished, with a winner or a tie
+    fn is_over(&self) -> bool {
+        self.0.get_winner() != GameState::InProgress
+    }
+    /// The player that will make the next move
+    #[getter]
+    fn current_player(&self) -> Player {
+        self.0.current_player.into()
+    }
+    /// The board the current player was sent to, as `(row, col)`, or `None` before the first move
+    #[getter]
+    fn last_move_cords(&self) -> Option<(usize, usize)> {
+        self.0.last_move_cords
+    }
+    /// The square at the given square coordinates: `"X"`, `"O"` or `"."`
+    fn square_at(&self, row: usize, col: usize) -> PyResult<String> {
+        if row >= GRID_SIZE || col >= GRID_SIZE {
+            return Err(PyValueError::new_err("square coordinates must be 0 to 8"));
+        }
+        Ok(self.0.square_at(row, col).to_string())
+    }
+    /// The game as a `float32` array of shape `(3, 9, 9)`, indexed by square coordinates.
+    /// The planes are the `X` squares, the `O` squares and the legal moves
+    fn tensor<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray3<f32>>> {
+        const PLANE: usize = GRID_SIZE * GRID_SIZE;
+        let mut data = vec![0.0; 3 * PLANE];
+        for ((row, col), square) in self.0.iter_squares() {
+            let plane = match square {
+                Square::Occupied(super_ttt::Player::X) => 0,
+                Square::Occupied(super_ttt::Player::O) => 1,
+                Square::Empty => continue,
+            };
+            data[plane * PLANE + row * GRID_SIZE + col] = 1.0;
+        }
+        if self.0.get_winner() == GameState::InProgress {
+            for &(board_row, board_col, cell_row, cell_col) in &self.0.legal_moves() {
+                let (row, col) =
+                    super_ttt::to_square_coords(board_row, board_col, cell_row, cell_col);
+                data[2 * PLANE + row * GRID_SIZE + col] = 1.0;
+            }
+        }
+        PyArray1::from_vec(py, data).reshape([3, GRID_SIZE, GRID_SIZE])
+    }
+    /// A copy of this game
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+    /// A copy of this game, for `copy.copy`
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+    /// A copy of this game, for `copy.deepcopy`
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+    /// The board drawn as text
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+    /// A `Game.from_position(...)` call that recreates this game
+    fn __repr__(&self) -> String {
+        format!("Game.from_position({:?})", self.0.to_position_string())
+    }
+}
+
+/// Python bindings for the super tic tac toe rules engine
+#[pymodule]
+#[pyo3(name = "super_ttt")]
+fn super_ttt_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Game>()?;
+    m.add_class::<Player>()?;
+    m.add("InvalidMoveError", m.py().get_type::<InvalidMoveError>())?;
+    Ok(())
+}
@@ -0,0 +1,109 @@
+import copy
+
+import numpy as np
+import pytest
+
+from super_ttt import Game, InvalidMoveError, Player
+
+START = "/".join(["........."] * 9) + " X -"
+
+
+def play_doctest_game():
+    # The same moves as the example in the Rust docs for `Game`
+    game = Game()
+    game.make_move(0, 0, 1, 1)
+    game.make_move(1, 1, 0, 0)
+    game.make_move(0, 0, 2, 2)
+    game.make_move(2, 2, 0, 2)
+    game.make_move(0, 2, 1, 0)
+    return game
+
+
+def test_doctest_game():
+    game = play_doctest_game()
+    assert game.winner() is None
+    assert game.result() == "*"
+    assert not game.is_over()
+    assert game.current_player == Player.O
+    assert game.last_move_cords == (1, 0)
+
+
+def test_new_game():
+    game = Game()
+    assert game.current_player == Player.X
+    assert game.last_move_cords is None
+    assert len(game.legal_moves()) == 81
+    assert game.to_position() == START
+
+
+def test_invalid_moves():
+    game = Game()
+    game.make_move(0, 0, 1, 1)
+    with pytest.raises(InvalidMoveError):
+        game.make_move(0, 0, 1, 1)
+    with pytest.raises(InvalidMoveError):
+        game.make_move(2, 2, 0, 0)
+    with pytest.raises(ValueError):
+        game.make_move(1, 1, 3, 0)
+    assert game.current_player == Player.O
+
+
+def test_legal_moves_are_forced():
+    game = Game()
+    game.make_move(1, 1, 0, 2)
+    moves = game.legal_moves()
+    assert len(moves) == 9
+    assert all(move[:2] == (0, 2) for move in moves)
+
+
+def test_winner():
+    # X has won the top left and top middle boards and is sent to the top right one
+    rows = [
+        "XXXXXXXX.",
+        ".........",
+        ".........",
+        "OO.OO.OO.",
+        ".........",
+        ".........",
+        "OO.......",
+        ".........",
+        ".........",
+    ]
+    game = Game.from_position("/".join(rows) + " X 2")
+    assert game.winner() is None
+    game.make_move(0, 2, 0, 2)
+    assert game.winner() == Player.X
+    assert game.result() == "1-0"
+    assert game.is_over()
+
+
+def test_copy_is_independent():
+    game = play_doctest_game()
+    for clone in [game.copy(), copy.copy(game), copy.deepcopy(game)]:
+        assert clone == game
+        clone.make_move(*clone.legal_moves()[0])
+        assert clone != game
+
+
+def test_position_round_trip():
+    game = play_doctest_game()
+    assert Game.from_position(game.to_position()) == game
+    assert Game.from_position(START) == Game()
+    assert eval(repr(game)) == game
+    with pytest.raises(ValueError):
+        Game.from_position("not a position")
+
+
+def test_tensor():
+    game = play_doctest_game()
+    tensor = game.tensor()
+    assert tensor.shape == (3, 9, 9)
+    assert tensor.dtype == np.float32
+    assert tensor[0].sum() == 3
+    assert tensor[1].sum() == 2
+    assert tensor[0, 1, 1] == 1
+    assert tensor[1, 3, 3] == 1
+    assert game.square_at(1, 1) == "X"
+    assert tensor[2].sum() == len(game.legal_moves())
+    for board_row, board_col, cell_row, cell_col in game.legal_moves():
+        assert tensor[2, board_row * 3 + cell_row, board_col * 3 + cell_col] == 1
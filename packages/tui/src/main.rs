@@ -29,11 +29,11 @@ fn main() {
         }
 
         if let GameState::Winner(player) = game.get_winner() {
-            println!("{:?} won", player);
+            println!("{} won", player);
             break;
         }
         let current_player = game.current_player;
-        println!("Current player: {:?}", current_player);
+        println!("Current player: {}", current_player);
 
         println!("Enter the row and column for your move (e.g., 0 0 1 1):");
         // let line = iterator.next().unwrap().unwrap();
@@ -44,7 +44,7 @@ fn main() {
         match game.make_move(bx, by, cx, cy) {
             Ok(_) => {
                 println!(
-                    "{:?} moved in board ({}, {}) in cell ({}, {})",
+                    "{} moved in board ({}, {}) in cell ({}, {})",
                     current_player, bx, by, cx, cy
                 )
             }
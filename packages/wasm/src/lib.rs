@@ -0,0 +1,199 @@
+//! # Super Tic Tac Toe for JavaScript
+//! [wasm-bindgen](https://rustwasm.github.io/docs/wasm-bindgen/) bindings for [`super_ttt`],
+//! so the rules can be used from plain JavaScript or TypeScript.
+//! `wasm-pack build packages/wasm` generates the package along with its TypeScript definitions.
+use super_ttt::errors::InvalidMoveError;
+use super_ttt::{notation, GameState, BOARD_SIZE};
+use wasm_bindgen::prelude::*;
+
+/// A player
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Player {
+    /// Always moves first
+    X = "X",
+    O = "O",
+}
+
+impl From<super_ttt::Player> for Player {
+    fn from(player: super_ttt::Player) -> Self {
+        match player {
+            super_ttt::Player::X => Player::X,
+            super_ttt::Player::O => Player::O,
+        }
+    }
+}
+
+/// The state of a game
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    InProgress = "*",
+    XWon = "1-0",
+    OWon = "0-1",
+    Tie = "½-½",
+}
+
+impl From<GameState> for GameResult {
+    fn from(state: GameState) -> Self {
+        match state {
+            GameState::InProgress => GameResult::InProgress,
+            GameState::Winner(super_ttt::Player::X) => GameResult::XWon,
+            GameState::Winner(super_ttt::Player::O) => GameResult::OWon,
+            GameState::Tie => GameResult::Tie,
+        }
+    }
+}
+
+/// A move: the board to play in and the square within that board.
+/// Every coordinate is between 0 and 2, so the fields are read-only from JavaScript
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    #[wasm_bindgen(readonly, js_name = boardRow)]
+    pub board_row: usize,
+    #[wasm_bindgen(readonly, js_name = boardCol)]
+    pub board_col: usize,
+    #[wasm_bindgen(readonly, js_name = cellRow)]
+    pub cell_row: usize,
+    #[wasm_bindgen(readonly, js_name = cellCol)]
+    pub cell_col: usize,
+}
+
+impl From<super_ttt::Move> for Move {
+    fn from((board_row, board_col, cell_row, cell_col): super_ttt::Move) -> Self {
+        Move {
+            board_row,
+            board_col,
+            cell_row,
+            cell_col,
+        }
+    }
+}
+impl From<Move> for super_ttt::Move {
+    fn from(move_: Move) -> Self {
+        (
+            move_.board_row,
+            move_.board_col,
+            move_.cell_row,
+            move_.cell_col,
+        )
+    }
+}
+
+#[wasm_bindgen]
+impl Move {
+    /// A move from its coordinates. Throws if one of them isn't between 0 and 2
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        board_row: usize,
+        board_col: usize,
+        cell_row: usize,
+        cell_col: usize,
+    ) -> Result<Move, JsError> {
+        if [board_row, board_col, cell_row, cell_col]
+            .iter()
+            .any(|&coordinate| coordinate >= BOARD_SIZE)
+        {
+            return Err(JsError::new(&InvalidMoveError::OutOfBounds.to_string()));
+        }
+        Ok(Move {
+            board_row,
+            board_col,
+            cell_row,
+            cell_col,
+        })
+    }
+    /// Read a move in move notation, like `e5`
+    pub fn parse(notation: &str) -> Result<Move, JsError> {
+        notation::parse_move(notation)
+            .map(Move::from)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+    /// The move in move notation, like `e5`
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_notation(&self) -> String {
+        notation::move_to_string((*self).into())
+    }
+}
+
+/// The 9x9 super tic tac toe game. `X` starts
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Game(super_ttt::Game);
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Game {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Game {
+        Game(super_ttt::Game::new())
+    }
+    /// Read a game from a position string. Throws if it isn't valid
+    #[wasm_bindgen(js_name = fromPosition)]
+    pub fn from_position(position: &str) -> Result<Game, JsError> {
+        super_ttt::Game::from_position_string(position)
+            .map(Game)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+    /// Write this game as a position string
+    #[wasm_bindgen(js_name = toPosition)]
+    pub fn to_position(&self) -> String {
+        self.0.to_position_string()
+    }
+    /// Make a move for the current player. Throws if the move isn't allowed
+    #[wasm_bindgen(js_name = makeMove)]
+    pub fn make_move(&mut self, move_: &Move) -> Result<(), JsError> {
+        self.0
+            .make_move(
+                move_.board_row,
+                move_.board_col,
+                move_.cell_row,
+                move_.cell_col,
+            )
+            .map(|_| ())
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+    /// Every move `makeMove` accepts
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.0.legal_moves().iter().map(|&m| m.into()).collect()
+    }
+    /// The state of the game
+    pub fn result(&self) -> GameResult {
+        self.0.get_winner().into()
+    }
+    /// The winner, or `undefined` if the game is tied or still in progress
+    pub fn winner(&self) -> Option<Player> {
+        match self.0.get_winner() {
+            GameState::Winner(player) => Some(player.into()),
+            _ => None,
+        }
+    }
+    /// The player that will make the next move
+    #[wasm_bindgen(getter, js_name = currentPlayer)]
+    pub fn current_player(&self) -> Player {
+        self.0.current_player.into()
+    }
+    /// The square at the given square coordinates (0 to 8): `"X"`, `"O"` or `"."`
+    #[wasm_bindgen(js_name = squareAt)]
+    pub fn square_at(&self, row: usize, col: usize) -> Option<String> {
+        (row < super_ttt::GRID_SIZE && col < super_ttt::GRID_SIZE)
+            .then(|| self.0.square_at(row, col).to_string())
+    }
+    /// A copy of this game
+    #[wasm_bindgen(js_name = clone)]
+    pub fn copy(&self) -> Game {
+        self.clone()
+    }
+    /// The game drawn as a grid
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_display_string(&self) -> String {
+        self.0.to_string()
+    }
+}
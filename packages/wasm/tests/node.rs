@@ -0,0 +1,50 @@
+//! Run with `wasm-pack test --node packages/wasm`
+#![cfg(target_arch = "wasm32")]
+
+use super_ttt_wasm::{Game, GameResult, Move, Player};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn play_doctest_game() -> Game {
+    // The same moves as the example in the Rust docs for `Game`
+    let mut game = Game::new();
+    for notation in ["b2", "d4", "c3", "i7", "g2"] {
+        game.make_move(&Move::parse(notation).unwrap()).unwrap();
+    }
+    game
+}
+
+#[wasm_bindgen_test]
+fn doctest_game() {
+    let game = play_doctest_game();
+    assert_eq!(game.result(), GameResult::InProgress);
+    assert_eq!(game.winner(), None);
+    assert_eq!(game.current_player(), Player::O);
+    assert_eq!(game.legal_moves().len(), 9);
+}
+
+#[wasm_bindgen_test]
+fn invalid_moves_throw() {
+    let mut game = Game::new();
+    game.make_move(&Move::new(0, 0, 1, 1).unwrap()).unwrap();
+    assert!(game.make_move(&Move::new(0, 0, 1, 1).unwrap()).is_err());
+    assert!(game.make_move(&Move::new(2, 2, 0, 0).unwrap()).is_err());
+    assert!(Move::parse("z9").is_err());
+    assert_eq!(game.current_player(), Player::O);
+}
+
+#[wasm_bindgen_test]
+fn positions_round_trip() {
+    let game = play_doctest_game();
+    assert_eq!(Game::from_position(&game.to_position()).unwrap(), game);
+    assert_eq!(game.copy(), game);
+    assert!(Game::from_position("not a position").is_err());
+    assert_eq!(game.square_at(1, 1).as_deref(), Some("X"));
+    assert_eq!(game.square_at(9, 0), None);
+}
+
+#[wasm_bindgen_test]
+fn move_notation() {
+    assert_eq!(Move::new(1, 1, 1, 1).unwrap().to_notation(), "e5");
+    assert_eq!(Move::parse("a1").unwrap(), Move::new(0, 0, 0, 0).unwrap());
+    assert!(Move::new(7, 0, 0, 0).is_err());
+}